@@ -0,0 +1,59 @@
+-- The schema inherited from the original Rails app. Tables are only created if they don't already
+-- exist, so that this can be applied to databases which were created by Rails.
+
+CREATE TABLE IF NOT EXISTS users (
+  id SERIAL PRIMARY KEY,
+  email_address VARCHAR(255),
+  username VARCHAR(255),
+  fullname VARCHAR(255) NOT NULL,
+  openid VARCHAR(255),
+  created_at TIMESTAMP NOT NULL,
+  updated_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contexts (
+  id SERIAL PRIMARY KEY,
+  name VARCHAR(255) NOT NULL,
+  description TEXT NOT NULL DEFAULT '',
+  created_at TIMESTAMP NOT NULL,
+  updated_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contexts_users (
+  context_id INTEGER NOT NULL REFERENCES contexts (id),
+  user_id INTEGER NOT NULL REFERENCES users (id)
+);
+
+CREATE TABLE IF NOT EXISTS quotes (
+  id SERIAL PRIMARY KEY,
+  quote_text TEXT NOT NULL,
+  context_id INTEGER NOT NULL REFERENCES contexts (id),
+  quoter_id INTEGER NOT NULL REFERENCES users (id),
+  quotee_id INTEGER NOT NULL REFERENCES users (id),
+  created_at TIMESTAMP NOT NULL,
+  updated_at TIMESTAMP NOT NULL,
+  hidden BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS comments (
+  id SERIAL PRIMARY KEY,
+  quote_id INTEGER NOT NULL REFERENCES quotes (id),
+  user_id INTEGER NOT NULL REFERENCES users (id),
+  body TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  updated_at TIMESTAMP NOT NULL
+);
+
+-- Rails didn't enforce unique memberships, but `User::join_context` relies on it.
+DELETE FROM contexts_users AS a
+  USING contexts_users AS b
+  WHERE a.ctid < b.ctid AND a.context_id = b.context_id AND a.user_id = b.user_id;
+CREATE UNIQUE INDEX IF NOT EXISTS index_contexts_users_on_context_id_and_user_id
+  ON contexts_users (context_id, user_id);
+
+CREATE INDEX IF NOT EXISTS index_contexts_users_on_user_id ON contexts_users (user_id);
+CREATE INDEX IF NOT EXISTS index_quotes_on_context_id ON quotes (context_id);
+CREATE INDEX IF NOT EXISTS index_quotes_on_quotee_id ON quotes (quotee_id);
+CREATE INDEX IF NOT EXISTS index_quotes_on_created_at ON quotes (created_at);
+CREATE INDEX IF NOT EXISTS index_comments_on_quote_id ON comments (quote_id);
+CREATE INDEX IF NOT EXISTS index_comments_on_user_id ON comments (user_id);
@@ -0,0 +1,5 @@
+-- Each membership has a role: the owner can do anything to the context, admins can edit it and
+-- moderate its members and quotes, and members can only add quotes.
+ALTER TABLE contexts_users
+  ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'member'
+  CHECK (role IN ('owner', 'admin', 'member'));
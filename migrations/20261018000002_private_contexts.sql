@@ -0,0 +1,2 @@
+-- Quotes and comments in private contexts are only shown to members of the context.
+ALTER TABLE contexts ADD COLUMN IF NOT EXISTS private BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,13 @@
+-- Links which let whoever visits them join a context. They are deleted when revoked, or when used
+-- if they are single-use.
+CREATE TABLE IF NOT EXISTS invitations (
+  id SERIAL PRIMARY KEY,
+  context_id INTEGER NOT NULL REFERENCES contexts (id),
+  token TEXT NOT NULL UNIQUE,
+  created_by INTEGER NOT NULL REFERENCES users (id),
+  single_use BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMP NOT NULL,
+  expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS index_invitations_on_context_id ON invitations (context_id);
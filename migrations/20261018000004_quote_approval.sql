@@ -0,0 +1,19 @@
+-- Whether quotes about a user must be approved by them before they are shown.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS quotes_need_approval BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Quotes awaiting approval by their quotee are also hidden, so that they are left out of listings
+-- until they are approved. Rejecting a quote leaves it hidden.
+ALTER TABLE quotes ADD COLUMN IF NOT EXISTS pending BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Messages shown to a user about things that other people have done to their quotes.
+CREATE TABLE IF NOT EXISTS notifications (
+  id SERIAL PRIMARY KEY,
+  user_id INTEGER NOT NULL REFERENCES users (id),
+  message TEXT NOT NULL,
+  -- The path of a page to go to about the notification, if any.
+  link TEXT,
+  read BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS index_notifications_on_user_id ON notifications (user_id);
@@ -0,0 +1,12 @@
+-- Personal tokens which let scripts use the API as a user. Only a hash of each token is stored, as
+-- the token itself is only shown once when it is created.
+CREATE TABLE IF NOT EXISTS api_tokens (
+  id SERIAL PRIMARY KEY,
+  user_id INTEGER NOT NULL REFERENCES users (id),
+  name TEXT NOT NULL,
+  token_hash TEXT NOT NULL UNIQUE,
+  created_at TIMESTAMP NOT NULL,
+  last_used_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS index_api_tokens_on_user_id ON api_tokens (user_id);
@@ -0,0 +1,6 @@
+-- Indexes for full-text search of quotes and comments. Queries must use the same expressions for
+-- these to be used.
+CREATE INDEX IF NOT EXISTS index_quotes_on_quote_text_tsvector
+  ON quotes USING GIN (to_tsvector('english', quote_text));
+CREATE INDEX IF NOT EXISTS index_comments_on_body_tsvector
+  ON comments USING GIN (to_tsvector('english', body));
@@ -0,0 +1,10 @@
+-- Listings of quotes and comments are ordered by creation time, with the ID to break ties, so that
+-- the page after a given quote or comment can be found without skipping over all the earlier ones.
+CREATE INDEX IF NOT EXISTS index_quotes_on_created_at_and_id ON quotes (created_at, id);
+CREATE INDEX IF NOT EXISTS index_quotes_on_context_id_and_created_at_and_id
+  ON quotes (context_id, created_at, id);
+CREATE INDEX IF NOT EXISTS index_quotes_on_quotee_id_and_created_at_and_id
+  ON quotes (quotee_id, created_at, id);
+CREATE INDEX IF NOT EXISTS index_comments_on_created_at_and_id ON comments (created_at, id);
+CREATE INDEX IF NOT EXISTS index_comments_on_quote_id_and_created_at_and_id
+  ON comments (quote_id, created_at, id);
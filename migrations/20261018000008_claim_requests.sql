@@ -0,0 +1,10 @@
+-- Requests from people who have logged in with Google to become a placeholder user. A request only
+-- takes effect once someone who can vouch for the placeholder user confirms it.
+CREATE TABLE IF NOT EXISTS claim_requests (
+  id SERIAL PRIMARY KEY,
+  user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+  email_address TEXT NOT NULL,
+  fullname TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL,
+  UNIQUE (user_id, email_address)
+);
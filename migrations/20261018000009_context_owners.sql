@@ -0,0 +1,12 @@
+-- Memberships from before roles were added all became plain members, leaving their contexts with
+-- no owner. Make the earliest remaining member of each such context its owner. The table has no
+-- timestamps, so insertion order is the best there is.
+UPDATE contexts_users
+  SET role = 'owner'
+  FROM (
+    SELECT DISTINCT ON (context_id) context_id, ctid AS first_ctid
+      FROM contexts_users
+      WHERE context_id NOT IN (SELECT context_id FROM contexts_users WHERE role = 'owner')
+      ORDER BY context_id, ctid
+  ) AS first_members
+  WHERE contexts_users.ctid = first_members.first_ctid;
@@ -0,0 +1,4 @@
+-- Whether a quote was hidden by its quotee, either by hiding it or by rejecting it, rather than by
+-- a moderator. Only the quotee may show such a quote again. Quotes rejected before this was added
+-- can't be told apart from those hidden by moderators, so they are left as they are.
+ALTER TABLE quotes ADD COLUMN IF NOT EXISTS hidden_by_quotee BOOLEAN NOT NULL DEFAULT FALSE;
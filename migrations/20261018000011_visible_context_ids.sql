@@ -0,0 +1,10 @@
+-- The IDs of the contexts whose quotes the given user can see: all the public contexts, and the
+-- private ones which they are a member of. Only public contexts are visible if the user is NULL.
+-- Every query for quotes or comments which someone views must filter them by this.
+CREATE OR REPLACE FUNCTION visible_context_ids(viewer_id INTEGER) RETURNS SETOF INTEGER
+  LANGUAGE SQL STABLE
+  AS $$
+    SELECT id FROM contexts WHERE NOT private
+    UNION
+    SELECT context_id FROM contexts_users WHERE user_id = viewer_id
+  $$;
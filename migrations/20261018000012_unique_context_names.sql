@@ -0,0 +1,5 @@
+-- Context names must be unique ignoring case. The form checks this first to give a friendly error,
+-- but two requests at once could both pass that check. If this fails then contexts with the same
+-- name must be renamed first, which can be found with:
+--   SELECT LOWER(name), ARRAY_AGG(id) FROM contexts GROUP BY LOWER(name) HAVING COUNT(*) > 1;
+CREATE UNIQUE INDEX IF NOT EXISTS index_contexts_on_lower_name ON contexts (LOWER(name));
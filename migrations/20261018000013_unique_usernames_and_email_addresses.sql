@@ -0,0 +1,10 @@
+-- Usernames and email addresses must be unique ignoring case. The forms check these first to give
+-- friendly errors, but two requests at once could both pass those checks. If this fails then the
+-- users sharing a username or email address must be merged or changed first, which can be found
+-- with:
+--   SELECT LOWER(username), ARRAY_AGG(id) FROM users GROUP BY LOWER(username) HAVING COUNT(*) > 1;
+--   SELECT LOWER(email_address), ARRAY_AGG(id) FROM users GROUP BY LOWER(email_address)
+--     HAVING COUNT(*) > 1;
+CREATE UNIQUE INDEX IF NOT EXISTS index_users_on_lower_username ON users (LOWER(username));
+CREATE UNIQUE INDEX IF NOT EXISTS index_users_on_lower_email_address
+  ON users (LOWER(email_address));
@@ -1,2 +0,0 @@
-pub mod comments;
-pub mod quotes;
@@ -0,0 +1,163 @@
+use crate::{
+    config::Config,
+    errors::InternalError,
+    model::{self, Quote, User},
+};
+use clap::{Parser, Subcommand};
+use eyre::{bail, Report};
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+
+/// A website for collecting funny quotes from groups of friends.
+///
+/// With no subcommand, applies any pending database migrations then runs the web server.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Applies any pending database migrations, then runs the web server.
+    Serve,
+    #[command(flatten)]
+    Maintenance(MaintenanceCommand),
+    /// Checks that the config file can be parsed and the database can be reached, then exits.
+    CheckConfig,
+}
+
+/// Subcommands which do something to the database then exit.
+#[derive(Subcommand)]
+pub enum MaintenanceCommand {
+    /// Applies any pending database migrations, then exits.
+    Migrate,
+    /// Adds a new user who can log in with the given email address.
+    CreateUser {
+        /// The user's full name, as shown on their quotes.
+        fullname: String,
+        /// The email address of the user's Google account.
+        email_address: String,
+        /// An optional short name for the user.
+        #[arg(long)]
+        username: Option<String>,
+    },
+    /// Moves all quotes, comments and context memberships of one user to another, then deletes the
+    /// first user.
+    MergeUsers {
+        /// The ID of the user to delete.
+        from_user_id: i32,
+        /// The ID of the user to keep.
+        into_user_id: i32,
+    },
+    /// Hides a quote so that it no longer appears anywhere on the site.
+    HideQuote {
+        /// The ID of the quote to hide.
+        quote_id: i32,
+        /// Makes the quote visible again rather than hiding it.
+        #[arg(long)]
+        unhide: bool,
+    },
+}
+
+/// Runs the given maintenance subcommand.
+pub async fn run(command: MaintenanceCommand, pool: &Pool<Postgres>) -> Result<(), Report> {
+    match command {
+        MaintenanceCommand::Migrate => {
+            model::migrate(pool).await?;
+            println!("Database is up to date.");
+        }
+        MaintenanceCommand::CreateUser {
+            fullname,
+            email_address,
+            username,
+        } => create_user(pool, &fullname, &email_address, username.as_deref()).await?,
+        MaintenanceCommand::MergeUsers {
+            from_user_id,
+            into_user_id,
+        } => {
+            if from_user_id == into_user_id {
+                bail!("Can't merge a user into themself.");
+            }
+            User::merge(pool, from_user_id, into_user_id).await?;
+            println!("Merged user {} into {}.", from_user_id, into_user_id);
+        }
+        MaintenanceCommand::HideQuote { quote_id, unhide } => {
+            let quote = match Quote::fetch_one_for_admin(pool, quote_id).await {
+                Err(InternalError::NotFound) => bail!("No quote with ID {}.", quote_id),
+                result => result?,
+            };
+            // Unhiding a pending quote would show it without its quotee's approval, and likewise
+            // for one which its quotee hid or rejected.
+            if unhide && quote.pending {
+                bail!(
+                    "Quote {} is waiting for its quotee's approval, so can't be unhidden.",
+                    quote_id
+                );
+            }
+            if unhide && quote.hidden_by_quotee {
+                bail!(
+                    "Quote {} was hidden by its quotee, so only they can unhide it.",
+                    quote_id
+                );
+            }
+            Quote::set_hidden(pool, quote_id, !unhide, false).await?;
+            if unhide {
+                println!("Unhid quote {}.", quote_id);
+            } else {
+                println!("Hid quote {}.", quote_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the database given in the config can be reached, and prints the main settings.
+pub async fn check_config(config: &Config) -> Result<(), Report> {
+    let pool = match PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&config.postgres_uri)
+        .await
+    {
+        Ok(pool) => pool,
+        Err(e) => bail!("Config is valid, but the database can't be reached: {}", e),
+    };
+    sqlx::query("SELECT 1").execute(&pool).await?;
+    println!("Config is valid and the database is reachable.");
+    println!("Listening address: {}", config.bind_address);
+    println!("Base URL: {}", config.base_url);
+    println!("Admin user IDs: {:?}", config.admin_user_ids);
+    Ok(())
+}
+
+async fn create_user(
+    pool: &Pool<Postgres>,
+    fullname: &str,
+    email_address: &str,
+    username: Option<&str>,
+) -> Result<(), Report> {
+    if fullname.trim().is_empty() {
+        bail!("Full name must not be blank.");
+    }
+    if !User::is_valid_email_address(email_address) {
+        bail!("Invalid email address {:?}.", email_address);
+    }
+    if User::fetch_by_email(pool, email_address).await?.is_some() {
+        bail!(
+            "A user with email address {} already exists.",
+            email_address
+        );
+    }
+    if let Some(username) = username {
+        if !User::is_valid_username(username) {
+            bail!("Invalid username {:?}.", username);
+        }
+        if User::fetch_by_username(pool, username).await?.is_some() {
+            bail!("Username {} is already taken.", username);
+        }
+    }
+
+    let user = User::create(pool, fullname.trim(), username, email_address).await?;
+    println!("Created user {} with ID {}.", user.fullname, user.id);
+    Ok(())
+}
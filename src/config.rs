@@ -1,5 +1,5 @@
 use eyre::{bail, Report, WrapErr};
-use serde::{Deserialize, Deserializer};
+use serde::{de, Deserialize, Deserializer};
 use std::{
     fs::read_to_string,
     net::SocketAddr,
@@ -30,6 +30,15 @@ pub struct Config {
         rename = "session_duration_seconds"
     )]
     pub session_duration: Duration,
+    /// The IDs of users who may use the admin pages.
+    #[serde(default)]
+    pub admin_user_ids: Vec<i32>,
+    /// The most entries to include in each page of a feed.
+    #[serde(
+        default = "default_feed_entries",
+        deserialize_with = "de_nonzero_usize"
+    )]
+    pub feed_entries: usize,
 }
 
 impl Config {
@@ -51,6 +60,10 @@ impl Config {
         Ok(toml::from_str(&config_file)?)
     }
 
+    pub fn is_admin(&self, user_id: i32) -> bool {
+        self.admin_user_ids.contains(&user_id)
+    }
+
     pub fn absolute_url(&self, path: &str) -> String {
         format!("{}{}", self.base_url, path)
     }
@@ -73,11 +86,22 @@ fn default_session_duration() -> Duration {
     Duration::from_secs(30 * 24 * 60 * 60)
 }
 
+fn default_feed_entries() -> usize {
+    50
+}
+
 pub fn de_duration_seconds<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
     let seconds = u64::deserialize(d)?;
     Ok(Duration::from_secs(seconds))
 }
 
+pub fn de_nonzero_usize<'de, D: Deserializer<'de>>(d: D) -> Result<usize, D::Error> {
+    match usize::deserialize(d)? {
+        0 => Err(de::Error::custom("must be at least 1")),
+        value => Ok(value),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,4 +117,18 @@ mod tests {
     fn empty_config() {
         toml::from_str::<Config>("").unwrap_err();
     }
+
+    /// Feeds must have at least one entry in each page.
+    #[test]
+    fn zero_feed_entries() {
+        toml::from_str::<Config>(
+            r#"
+            postgres_uri = "postgres://localhost/quotebook"
+            google_client_id = "abc123"
+            secret = "secret"
+            feed_entries = 0
+            "#,
+        )
+        .unwrap_err();
+    }
 }
@@ -0,0 +1,68 @@
+use crate::{errors::InternalError, model::User, session::Session};
+use askama::Template;
+use axum::{
+    extract::{Extension, Form},
+    response::{Html, Redirect},
+};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+use tower_cookies::{Cookie, Cookies};
+
+pub async fn merge_users_form(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+) -> Result<Html<String>, InternalError> {
+    // Only admins may merge users.
+    if !session.admin {
+        return Err(InternalError::Unauthorised);
+    }
+
+    let users = User::fetch_all(&pool).await?;
+
+    let template = MergeUsersTemplate { session, users };
+    Ok(Html(template.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "admin/merge_users.html")]
+struct MergeUsersTemplate {
+    session: Session,
+    users: Vec<User>,
+}
+
+/// The fields submitted by `admin/merge_users.html`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MergeUsersRequest {
+    from_user_id: i32,
+    into_user_id: i32,
+}
+
+pub async fn merge_users(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    cookies: Cookies,
+    Form(request): Form<MergeUsersRequest>,
+) -> Result<Redirect, InternalError> {
+    // Only admins may merge users.
+    if !session.admin {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if request.from_user_id == request.into_user_id {
+        cookies.add(Cookie::new("error", "Can't merge a user into themself."));
+        return Ok(Redirect::to("/admin/merge_users"));
+    }
+
+    let from_user = User::fetch_one(&pool, request.from_user_id).await?;
+    let into_user = User::fetch_one(&pool, request.into_user_id).await?;
+    User::merge(&pool, from_user.id, into_user.id).await?;
+    cookies.add(Cookie::new(
+        "notice",
+        format!(
+            "Merged {} ({}) into {} ({}).",
+            from_user.fullname, from_user.id, into_user.fullname, into_user.id
+        ),
+    ));
+
+    Ok(Redirect::to(&format!("/users/{}", into_user.id)))
+}
@@ -0,0 +1,174 @@
+use super::{CommentJson, CreatedJson, ErrorJson, ListJson};
+use crate::{
+    controllers::comments::CommentRequest,
+    errors::InternalError,
+    model::{CommentWithQuote, CommentWithQuotee, Quote},
+    pagination::{ListPagination, PaginationState, QueryListPage, LIST_PAGE_SIZE},
+    session::Session,
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/comments",
+    tag = "comments",
+    params(QueryListPage),
+    responses(
+        (status = OK, description = "A page of all comments, starting with the most recent", body = ListJson<CommentJson>),
+    ),
+)]
+pub async fn index(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Query(query): Query<QueryListPage>,
+) -> Result<Json<ListJson<CommentJson>>, InternalError> {
+    let (comments, pagination) = if let Some(after) = &query.after {
+        let comments = CommentWithQuotee::fetch_after(
+            &pool,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(comments, LIST_PAGE_SIZE)
+    } else {
+        let comment_count = CommentWithQuotee::count(&pool, session.current_user_id()).await?;
+        let numbered = PaginationState::new(comment_count, LIST_PAGE_SIZE, query.page);
+        let comments = CommentWithQuotee::fetch_page(
+            &pool,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(comments, numbered)
+    };
+
+    Ok(Json(ListJson::listing(comments, &pagination)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/quotes/{quote_id}/comments",
+    tag = "comments",
+    params(("quote_id" = i32, Path), QueryListPage),
+    responses(
+        (status = OK, description = "A page of the comments on the quote, starting with the oldest", body = ListJson<CommentJson>),
+        (status = NOT_FOUND, description = "There is no such quote which you may see"),
+    ),
+)]
+pub async fn index_for_quote(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    Query(query): Query<QueryListPage>,
+) -> Result<Json<ListJson<CommentJson>>, InternalError> {
+    // Make sure the quote exists.
+    Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+    let (comments, pagination) = if let Some(after) = &query.after {
+        let comments = CommentWithQuote::fetch_after_for_quote(
+            &pool,
+            quote_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(comments, LIST_PAGE_SIZE)
+    } else {
+        let comment_count =
+            CommentWithQuote::count_for_quote(&pool, quote_id, session.current_user_id()).await?;
+        let numbered = PaginationState::new(comment_count, LIST_PAGE_SIZE, query.page);
+        let comments = CommentWithQuote::fetch_page_for_quote(
+            &pool,
+            quote_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(comments, numbered)
+    };
+
+    Ok(Json(ListJson::listing(comments, &pagination)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/quotes/{quote_id}/comments/{comment_id}",
+    tag = "comments",
+    params(("quote_id" = i32, Path), ("comment_id" = i32, Path)),
+    responses(
+        (status = OK, description = "The comment", body = CommentJson),
+        (status = NOT_FOUND, description = "There is no such comment which you may see"),
+    ),
+)]
+pub async fn show(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((quote_id, comment_id)): Path<(i32, i32)>,
+) -> Result<Json<CommentJson>, InternalError> {
+    let comment =
+        CommentWithQuote::fetch_one(&pool, quote_id, comment_id, session.current_user_id()).await?;
+
+    Ok(Json(comment.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/quotes/{quote_id}/comments",
+    tag = "comments",
+    params(("quote_id" = i32, Path)),
+    request_body = CommentRequest,
+    responses(
+        (status = CREATED, description = "The comment was added", body = CreatedJson),
+        (status = UNAUTHORIZED, description = "You aren't logged in"),
+        (status = NOT_FOUND, description = "There is no such quote which you may see"),
+        (status = UNPROCESSABLE_ENTITY, description = "The comment isn't valid", body = ErrorJson),
+    ),
+    security(("api_token" = [])),
+)]
+pub async fn create(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    Json(request): Json<CommentRequest>,
+) -> Result<Response, InternalError> {
+    // Make sure the quote exists.
+    Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+
+    // There must be a user logged in.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+
+    if let Err(form) = request.validate() {
+        return Ok(ErrorJson {
+            error: form.error_messages,
+            possible_quotee_matches: None,
+        }
+        .into_response());
+    }
+
+    let comment_id = request.insert(&pool, quote_id, user.id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        [(
+            header::LOCATION,
+            format!("/api/v1/quotes/{}/comments/{}", quote_id, comment_id),
+        )],
+        Json(CreatedJson {
+            id: comment_id,
+            pending: None,
+        }),
+    )
+        .into_response())
+}
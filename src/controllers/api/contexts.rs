@@ -0,0 +1,96 @@
+use super::{ContextJson, ListJson, QuoteJson};
+use crate::{
+    errors::InternalError,
+    model::{Context, QuoteWithUsers},
+    pagination::{ListPagination, PaginationState, QueryListPage, LIST_PAGE_SIZE},
+    session::Session,
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/contexts",
+    tag = "contexts",
+    responses(
+        (status = OK, description = "All contexts, starting with the most recently created", body = ListJson<ContextJson>),
+    ),
+)]
+pub async fn index(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+) -> Result<Json<ListJson<ContextJson>>, InternalError> {
+    let contexts = Context::fetch_all(&pool, session.current_user_id()).await?;
+
+    Ok(Json(ListJson::all(contexts)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/contexts/{context_id}",
+    tag = "contexts",
+    params(("context_id" = i32, Path)),
+    responses(
+        (status = OK, description = "The context", body = ContextJson),
+        (status = NOT_FOUND, description = "There is no such context which you may see"),
+    ),
+)]
+pub async fn show(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(context_id): Path<i32>,
+) -> Result<Json<ContextJson>, InternalError> {
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+
+    Ok(Json(context.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/contexts/{context_id}/quotes",
+    tag = "contexts",
+    params(("context_id" = i32, Path), QueryListPage),
+    responses(
+        (status = OK, description = "A page of the quotes in the context, starting with the most recent", body = ListJson<QuoteJson>),
+        (status = NOT_FOUND, description = "There is no such context which you may see"),
+    ),
+)]
+pub async fn quotes(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(context_id): Path<i32>,
+    Query(query): Query<QueryListPage>,
+) -> Result<Json<ListJson<QuoteJson>>, InternalError> {
+    // Make sure the context exists.
+    Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after_for_context(
+            &pool,
+            context_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count =
+            QuoteWithUsers::count_for_context(&pool, context_id, session.current_user_id()).await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page_for_context(
+            &pool,
+            context_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
+
+    Ok(Json(ListJson::listing(quotes, &pagination)))
+}
@@ -0,0 +1,319 @@
+//! Version 1 of the JSON API, under `/api/v1`.
+//!
+//! This mirrors the HTML pages, with the same permissions and pagination. Lists of quotes and
+//! comments are paginated with the same `page` query parameter as the HTML pages, or with the
+//! `after` query parameter set to the `next_cursor` of the previous page, which stays fast however
+//! deep into the list the page is.
+//!
+//! An OpenAPI description of the API is generated from the handlers and types here, and served at
+//! `/api/openapi.json`.
+
+pub mod comments;
+pub mod contexts;
+pub mod quotes;
+pub mod search;
+pub mod users;
+
+use crate::{
+    controllers::{comments::CommentRequest, quotes::QuoteRequest},
+    model::{Comment, CommentWithQuote, CommentWithQuotee, Context, QuoteWithUsers, User},
+    pagination::{ListPagination, PaginationState},
+};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use sqlx::types::chrono::{DateTime, Utc};
+use utoipa::{
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct UserJson {
+    pub id: i32,
+    pub username: Option<String>,
+    pub fullname: String,
+}
+
+impl From<User> for UserJson {
+    fn from(user: User) -> Self {
+        // The email address and OpenID are deliberately left out, as they aren't shown to other
+        // people on the HTML pages either.
+        Self {
+            id: user.id,
+            username: user.username,
+            fullname: user.fullname,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ContextJson {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub private: bool,
+    /// This is left out when the context is part of a quote.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quotes_count: Option<i64>,
+}
+
+impl From<Context> for ContextJson {
+    fn from(context: Context) -> Self {
+        Self {
+            id: context.id,
+            name: context.name,
+            description: context.description,
+            private: context.private,
+            quotes_count: Some(context.quotes_count),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct QuoteJson {
+    pub id: i32,
+    pub quote_text: String,
+    pub quoter: UserJson,
+    pub quotee: UserJson,
+    pub context: ContextJson,
+    pub comments_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<QuoteWithUsers> for QuoteJson {
+    fn from(quote: QuoteWithUsers) -> Self {
+        Self {
+            id: quote.quote.id,
+            quote_text: quote.quote.quote_text,
+            quoter: quote.quoter.into(),
+            quotee: quote.quotee.into(),
+            context: ContextJson {
+                quotes_count: None,
+                ..quote.context.into()
+            },
+            comments_count: quote.comments_count,
+            created_at: quote.quote.created_at,
+            updated_at: quote.quote.updated_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct CommentJson {
+    pub id: i32,
+    pub quote_id: i32,
+    pub quote_text: String,
+    pub user: UserJson,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CommentJson {
+    fn new(comment: Comment, quote_text: String, user: User) -> Self {
+        Self {
+            id: comment.id,
+            quote_id: comment.quote_id,
+            quote_text,
+            user: user.into(),
+            body: comment.body,
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+        }
+    }
+}
+
+impl From<CommentWithQuote> for CommentJson {
+    fn from(comment: CommentWithQuote) -> Self {
+        Self::new(comment.comment, comment.quote_text, comment.user)
+    }
+}
+
+impl From<CommentWithQuotee> for CommentJson {
+    fn from(comment: CommentWithQuotee) -> Self {
+        Self::new(comment.comment, comment.quote_text, comment.user)
+    }
+}
+
+/// A list of things, with details of which page of the whole list it is if it is paginated.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ListJson<T> {
+    pub items: Vec<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PaginationJson>,
+    /// The `after` query parameter to fetch the next page, if there is one. This is left out if
+    /// the list can't be paginated by cursor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> ListJson<T> {
+    /// Converts a whole list of things.
+    fn all<U: Into<T>>(items: Vec<U>) -> Self {
+        Self {
+            items: items.into_iter().map(Into::into).collect(),
+            pagination: None,
+            next_cursor: None,
+        }
+    }
+
+    /// Converts the given page of a list of things.
+    fn page<U: Into<T>>(items: Vec<U>, pagination: &PaginationState) -> Self {
+        Self {
+            items: items.into_iter().map(Into::into).collect(),
+            pagination: Some(pagination.into()),
+            next_cursor: None,
+        }
+    }
+
+    /// Converts the given page of a list of things which may be paginated by cursor. Pages found
+    /// from a cursor don't include the numbered `pagination`.
+    fn listing<U: Into<T>>(items: Vec<U>, pagination: &ListPagination) -> Self {
+        Self {
+            items: items.into_iter().map(Into::into).collect(),
+            pagination: pagination.numbered.as_ref().map(Into::into),
+            next_cursor: pagination.next.map(|next| next.to_string()),
+        }
+    }
+}
+
+/// Where a page is in a paginated list. Pages are numbered from 0, as for the `page` query
+/// parameter.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, ToSchema)]
+pub struct PaginationJson {
+    pub page: usize,
+    pub page_count: usize,
+    pub per_page: usize,
+    pub total: usize,
+}
+
+impl From<&PaginationState> for PaginationJson {
+    fn from(pagination: &PaginationState) -> Self {
+        Self {
+            page: pagination.current_page.offset,
+            page_count: pagination.pages.page_count(),
+            per_page: pagination.pages.limit(),
+            total: pagination.pages.length(),
+        }
+    }
+}
+
+/// The response to a successful request to add something.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct CreatedJson {
+    pub id: i32,
+    /// Whether the new quote is waiting for its quotee to approve it before it is shown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending: Option<bool>,
+}
+
+/// The response to a request which was invalid.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ErrorJson {
+    pub error: String,
+    /// If the quotee of a new quote couldn't be found, the people they might have meant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub possible_quotee_matches: Option<Vec<UserJson>>,
+}
+
+impl IntoResponse for ErrorJson {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(self)).into_response()
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "theQuotebook API"),
+    paths(
+        comments::index,
+        comments::index_for_quote,
+        comments::show,
+        comments::create,
+        contexts::index,
+        contexts::show,
+        contexts::quotes,
+        quotes::index,
+        quotes::show,
+        quotes::create,
+        search::index,
+        users::show,
+        users::quotes,
+        users::relevant_quotes,
+        users::relevant_comments,
+    ),
+    components(schemas(CommentRequest, QuoteRequest)),
+    modifiers(&ApiTokenSecurity),
+)]
+pub struct ApiDoc;
+
+/// Adds the personal API tokens, sent in an `Authorization: Bearer` header, as a security scheme.
+struct ApiTokenSecurity;
+
+impl Modify for ApiTokenSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_default()
+            .add_security_scheme(
+                "api_token",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+pub async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_of_list() {
+        let pagination = PaginationState::new(12, 5, 1);
+        let list = ListJson::<i32>::page(vec![6, 7, 8, 9, 10], &pagination);
+        assert_eq!(
+            list.pagination,
+            Some(PaginationJson {
+                page: 1,
+                page_count: 3,
+                per_page: 5,
+                total: 12,
+            })
+        );
+    }
+
+    #[test]
+    fn openapi_describes_paths_and_security() {
+        let openapi = ApiDoc::openapi();
+        assert_eq!(openapi.paths.paths.len(), 13);
+        let quotes = &openapi.paths.paths["/api/v1/quotes"];
+        assert!(quotes.get.is_some());
+        assert!(quotes.post.is_some());
+        assert!(openapi
+            .components
+            .unwrap()
+            .security_schemes
+            .contains_key("api_token"));
+    }
+
+    #[test]
+    fn user_leaves_out_email_address() {
+        let user = User {
+            id: 42,
+            email_address: Some("someone@example.com".to_string()),
+            username: None,
+            fullname: "Some One".to_string(),
+            openid: None,
+        };
+        let json = serde_json::to_string(&UserJson::from(user)).unwrap();
+        assert_eq!(json, r#"{"id":42,"username":null,"fullname":"Some One"}"#);
+    }
+}
@@ -0,0 +1,123 @@
+use super::{CreatedJson, ErrorJson, ListJson, QuoteJson};
+use crate::{
+    controllers::quotes::QuoteRequest,
+    errors::InternalError,
+    model::QuoteWithUsers,
+    pagination::{ListPagination, PaginationState, QueryListPage, LIST_PAGE_SIZE},
+    session::Session,
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/quotes",
+    tag = "quotes",
+    params(QueryListPage),
+    responses(
+        (status = OK, description = "A page of all quotes, starting with the most recent", body = ListJson<QuoteJson>),
+    ),
+)]
+pub async fn index(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Query(query): Query<QueryListPage>,
+) -> Result<Json<ListJson<QuoteJson>>, InternalError> {
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after(
+            &pool,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count = QuoteWithUsers::count(&pool, session.current_user_id()).await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page(
+            &pool,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
+
+    Ok(Json(ListJson::listing(quotes, &pagination)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/quotes/{quote_id}",
+    tag = "quotes",
+    params(("quote_id" = i32, Path)),
+    responses(
+        (status = OK, description = "The quote", body = QuoteJson),
+        (status = NOT_FOUND, description = "There is no such quote which you may see"),
+    ),
+)]
+pub async fn show(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+) -> Result<Json<QuoteJson>, InternalError> {
+    let quote = QuoteWithUsers::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+
+    Ok(Json(quote.into()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/quotes",
+    tag = "quotes",
+    request_body = QuoteRequest,
+    responses(
+        (status = CREATED, description = "The quote was added", body = CreatedJson),
+        (status = UNAUTHORIZED, description = "You aren't logged in"),
+        (status = UNPROCESSABLE_ENTITY, description = "The quote isn't valid", body = ErrorJson),
+    ),
+    security(("api_token" = [])),
+)]
+pub async fn create(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Json(request): Json<QuoteRequest>,
+) -> Result<Response, InternalError> {
+    // There must be a user logged in.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+
+    let (context, quotee) = match request.validate(&pool, user.id).await? {
+        Ok(valid) => valid,
+        Err(form) => {
+            return Ok(ErrorJson {
+                error: form.error_messages,
+                possible_quotee_matches: form
+                    .possible_quotee_matches
+                    .map(|users| users.into_iter().map(Into::into).collect()),
+            }
+            .into_response());
+        }
+    };
+
+    let (quote_id, pending) = request.insert(&pool, user.id, &context, &quotee).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, format!("/api/v1/quotes/{}", quote_id))],
+        Json(CreatedJson {
+            id: quote_id,
+            pending: Some(pending),
+        }),
+    )
+        .into_response())
+}
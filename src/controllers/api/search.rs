@@ -0,0 +1,46 @@
+use super::{ListJson, QuoteJson};
+use crate::{
+    controllers::search::SearchQuery,
+    errors::InternalError,
+    model::QuoteWithUsers,
+    pagination::{PaginationState, LIST_PAGE_SIZE},
+    session::Session,
+};
+use axum::{
+    extract::{Extension, Query},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "quotes",
+    params(SearchQuery),
+    responses(
+        (status = OK, description = "A page of the quotes which match the search or have comments which do, starting with the best match in either the quote or one of its comments", body = ListJson<QuoteJson>),
+    ),
+)]
+pub async fn index(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<ListJson<QuoteJson>>, InternalError> {
+    let Some(search) = query.search() else {
+        return Ok(Json(ListJson::all(Vec::<QuoteWithUsers>::new())));
+    };
+
+    let quote_count =
+        QuoteWithUsers::count_matching(&pool, &search, session.current_user_id()).await?;
+    let pagination = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+    let quotes = QuoteWithUsers::fetch_page_matching(
+        &pool,
+        &search,
+        &pagination.pages,
+        &pagination.current_page,
+        session.current_user_id(),
+    )
+    .await?;
+
+    Ok(Json(ListJson::page(quotes, &pagination)))
+}
@@ -0,0 +1,174 @@
+use super::{CommentJson, ListJson, QuoteJson, UserJson};
+use crate::{
+    errors::InternalError,
+    model::{CommentWithQuotee, QuoteWithUsers, User},
+    pagination::{ListPagination, PaginationState, QueryListPage, LIST_PAGE_SIZE},
+    session::Session,
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}",
+    tag = "users",
+    params(("user_id" = i32, Path)),
+    responses(
+        (status = OK, description = "The user", body = UserJson),
+        (status = NOT_FOUND, description = "There is no such user"),
+    ),
+)]
+pub async fn show(
+    Extension(pool): Extension<Pool<Postgres>>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<UserJson>, InternalError> {
+    let user = User::fetch_one(&pool, user_id).await?;
+
+    Ok(Json(user.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}/quotes",
+    tag = "users",
+    params(("user_id" = i32, Path), QueryListPage),
+    responses(
+        (status = OK, description = "A page of the quotes of the user, starting with the most recent", body = ListJson<QuoteJson>),
+        (status = NOT_FOUND, description = "There is no such user"),
+    ),
+)]
+pub async fn quotes(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+    Query(query): Query<QueryListPage>,
+) -> Result<Json<ListJson<QuoteJson>>, InternalError> {
+    // Make sure the user exists.
+    User::fetch_one(&pool, user_id).await?;
+
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after_for_quotee(
+            &pool,
+            user_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count =
+            QuoteWithUsers::count_for_quotee(&pool, user_id, session.current_user_id()).await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page_for_quotee(
+            &pool,
+            user_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
+
+    Ok(Json(ListJson::listing(quotes, &pagination)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}/relevant_quotes",
+    tag = "users",
+    params(("user_id" = i32, Path), QueryListPage),
+    responses(
+        (status = OK, description = "A page of the quotes in contexts of which the user is a member, starting with the most recent", body = ListJson<QuoteJson>),
+        (status = NOT_FOUND, description = "There is no such user"),
+    ),
+)]
+pub async fn relevant_quotes(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+    Query(query): Query<QueryListPage>,
+) -> Result<Json<ListJson<QuoteJson>>, InternalError> {
+    // Make sure the user exists.
+    User::fetch_one(&pool, user_id).await?;
+
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after_for_user_contexts(
+            &pool,
+            user_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count =
+            QuoteWithUsers::count_for_user_contexts(&pool, user_id, session.current_user_id())
+                .await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page_for_user_contexts(
+            &pool,
+            user_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
+
+    Ok(Json(ListJson::listing(quotes, &pagination)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}/relevant_comments",
+    tag = "users",
+    params(("user_id" = i32, Path), QueryListPage),
+    responses(
+        (status = OK, description = "A page of the comments in contexts of which the user is a member, starting with the most recent", body = ListJson<CommentJson>),
+        (status = NOT_FOUND, description = "There is no such user"),
+    ),
+)]
+pub async fn relevant_comments(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+    Query(query): Query<QueryListPage>,
+) -> Result<Json<ListJson<CommentJson>>, InternalError> {
+    // Make sure the user exists.
+    User::fetch_one(&pool, user_id).await?;
+
+    let (comments, pagination) = if let Some(after) = &query.after {
+        let comments = CommentWithQuotee::fetch_after_for_user_contexts(
+            &pool,
+            user_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(comments, LIST_PAGE_SIZE)
+    } else {
+        let comment_count =
+            CommentWithQuotee::count_for_user_contexts(&pool, user_id, session.current_user_id())
+                .await?;
+        let numbered = PaginationState::new(comment_count, LIST_PAGE_SIZE, query.page);
+        let comments = CommentWithQuotee::fetch_page_for_user_contexts(
+            &pool,
+            user_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(comments, numbered)
+    };
+
+    Ok(Json(ListJson::listing(comments, &pagination)))
+}
@@ -0,0 +1,73 @@
+use super::users::EditTemplate;
+use crate::{errors::InternalError, model::ApiToken, session::Session};
+use askama::Template;
+use axum::{
+    extract::{Extension, Form, Path},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+use tower_cookies::{Cookie, Cookies};
+
+/// The longest name an API token may have.
+const MAX_NAME_LENGTH: usize = 100;
+
+pub async fn create(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<ApiTokenRequest>,
+) -> Result<Response, InternalError> {
+    // There must be a user logged in, and they can only create tokens for themself.
+    let user = session
+        .current_user
+        .clone()
+        .ok_or(InternalError::Unauthorised)?;
+    if user.id != user_id {
+        return Err(InternalError::Unauthorised);
+    }
+
+    let name = request.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        cookies.add(Cookie::new(
+            "error",
+            format!(
+                "API token names must be between 1 and {} characters long.",
+                MAX_NAME_LENGTH
+            ),
+        ));
+        return Ok(Redirect::to(&format!("/users/{}/edit", user_id)).into_response());
+    }
+
+    // Show the new token straight away rather than redirecting, as this is the only time it is
+    // available.
+    let token = ApiToken::create(&pool, user_id, name).await?;
+    let template = EditTemplate::with_new_api_token(&pool, session, user, token).await?;
+    Ok(Html(template.render()?).into_response())
+}
+
+/// The fields submitted by the API token form in `users/edit.html`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ApiTokenRequest {
+    name: String,
+}
+
+pub async fn destroy(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((user_id, token_id)): Path<(i32, i32)>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    // Users can only revoke their own tokens.
+    if !session.is_current_user(&user_id) {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if !ApiToken::delete(&pool, user_id, token_id).await? {
+        return Err(InternalError::NotFound);
+    }
+    cookies.add(Cookie::new("notice", "API token was revoked."));
+
+    Ok(Redirect::to(&format!("/users/{}/edit", user_id)))
+}
@@ -1,35 +1,67 @@
 use std::sync::Arc;
 
 use crate::{
-    atom::comments::comments_to_atom,
     config::Config,
     errors::InternalError,
+    feed::{comments::comments_to_feed, FeedFormat, FeedPage},
     filters,
-    model::{CommentWithQuote, CommentWithQuotee, Quote},
-    responses::Atom,
+    model::{Comment, CommentWithQuote, CommentWithQuotee, Quote},
+    pagination::{
+        ListPagination, PageOrGap, PaginationState, QueryFeedPage, QueryListPage, LIST_PAGE_SIZE,
+    },
+    responses::{CacheableHtml, Validated},
     session::Session,
 };
 use askama::Template;
 use axum::{
-    extract::{Extension, Path},
-    response::Html,
+    extract::{Extension, Form, Path, Query},
+    http::HeaderMap,
+    response::{Html, IntoResponse, Redirect, Response},
 };
+use serde::Deserialize;
 use sqlx::{Pool, Postgres};
+use tower_cookies::{Cookie, Cookies};
+use utoipa::ToSchema;
 
 pub async fn index(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Path(quote_id): Path<i32>,
-) -> Result<Html<String>, InternalError> {
-    let quote = Quote::fetch_one(&pool, quote_id).await?;
-    let comments = CommentWithQuote::fetch_all_for_quote(&pool, quote_id).await?;
+    Query(query): Query<QueryListPage>,
+) -> Result<CacheableHtml, InternalError> {
+    let quote = Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+    let (comments, pagination) = if let Some(after) = &query.after {
+        let comments = CommentWithQuote::fetch_after_for_quote(
+            &pool,
+            quote_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(comments, LIST_PAGE_SIZE)
+    } else {
+        let comment_count =
+            CommentWithQuote::count_for_quote(&pool, quote_id, session.current_user_id()).await?;
+        let numbered = PaginationState::new(comment_count, LIST_PAGE_SIZE, query.page);
+        let comments = CommentWithQuote::fetch_page_for_quote(
+            &pool,
+            quote_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(comments, numbered)
+    };
 
     let template = IndexTemplate {
         session,
         quote,
         comments,
+        pagination,
     };
-    Ok(Html(template.render()?))
+    Ok(CacheableHtml(template.render()?))
 }
 
 #[derive(Template)]
@@ -38,19 +70,51 @@ struct IndexTemplate {
     session: Session,
     quote: Quote,
     comments: Vec<CommentWithQuote>,
+    pagination: ListPagination,
 }
 
-pub async fn index_atom(
+pub async fn index_feed<F: FeedFormat>(
     Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
     Path(quote_id): Path<i32>,
-) -> Result<Atom, InternalError> {
-    let quote = Quote::fetch_one(&pool, quote_id).await?;
-    let comments = CommentWithQuotee::fetch_all_for_quote(&pool, quote_id).await?;
+    Query(query): Query<QueryFeedPage>,
+    headers: HeaderMap,
+) -> Result<Validated<F>, InternalError> {
+    let quote = Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+    let total =
+        CommentWithQuote::count_for_quote(&pool, quote_id, session.current_user_id()).await?;
+    let mut page = FeedPage::new(&query, total, config.feed_entries)?;
+    let comments = match page.archive_entries(total, config.feed_entries) {
+        Some((pages, archive)) => {
+            CommentWithQuotee::fetch_page_for_quote(
+                &pool,
+                quote_id,
+                &pages,
+                &archive,
+                session.current_user_id(),
+            )
+            .await?
+        }
+        None => {
+            let comments = CommentWithQuotee::fetch_after_for_quote(
+                &pool,
+                quote_id,
+                query.after.as_ref(),
+                config.feed_entries + 1,
+                session.current_user_id(),
+            )
+            .await?;
+            let (comments, pagination) =
+                ListPagination::after_cursor(comments, config.feed_entries);
+            page.next = pagination.next;
+            comments
+        }
+    };
     let title = format!("theQuotebook: Comments on {}", quote.quote_text);
     let path = format!("/quotes/{}/comments", quote_id);
 
-    Ok(Atom(comments_to_atom(comments, title, &path, &config)?))
+    comments_to_feed(comments, title, &path, &page, &config, &headers)
 }
 
 pub async fn show(
@@ -58,7 +122,8 @@ pub async fn show(
     session: Session,
     Path((quote_id, comment_id)): Path<(i32, i32)>,
 ) -> Result<Html<String>, InternalError> {
-    let comment = CommentWithQuote::fetch_one(&pool, quote_id, comment_id).await?;
+    let comment =
+        CommentWithQuote::fetch_one(&pool, quote_id, comment_id, session.current_user_id()).await?;
 
     let template = ShowTemplate { session, comment };
     Ok(Html(template.render()?))
@@ -70,3 +135,193 @@ struct ShowTemplate {
     session: Session,
     comment: CommentWithQuote,
 }
+
+pub async fn new(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+) -> Result<Html<String>, InternalError> {
+    let quote = Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+
+    // There must be a user logged in.
+    if !session.logged_in() {
+        return Err(InternalError::Unauthorised);
+    }
+
+    let template = NewTemplate {
+        session,
+        quote,
+        form: CommentForm::default(),
+    };
+    Ok(Html(template.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "comments/new.html")]
+struct NewTemplate {
+    session: Session,
+    quote: Quote,
+    form: CommentForm,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CommentForm {
+    pub error_messages: String,
+    pub body: String,
+}
+
+impl From<Comment> for CommentForm {
+    fn from(comment: Comment) -> Self {
+        Self {
+            error_messages: String::default(),
+            body: comment.body,
+        }
+    }
+}
+
+/// The fields submitted by `comments/form.html`, or to the JSON API.
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct CommentRequest {
+    body: String,
+}
+
+impl CommentRequest {
+    /// Checks that the request is valid.
+    ///
+    /// If there is a problem with the request, returns a form to show the user again with
+    /// appropriate error messages.
+    pub fn validate(&self) -> Result<(), CommentForm> {
+        if self.body.trim().is_empty() {
+            Err(CommentForm {
+                error_messages: "Comment can't be blank.".to_string(),
+                body: self.body.clone(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Adds the comment on the given quote by the given user, returning its ID.
+    pub async fn insert(
+        &self,
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        user_id: i32,
+    ) -> sqlx::Result<i32> {
+        Comment::create(pool, quote_id, user_id, self.body.trim()).await
+    }
+}
+
+pub async fn create(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<CommentRequest>,
+) -> Result<Response, InternalError> {
+    let quote = Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+
+    // There must be a user logged in.
+    let user = session
+        .current_user
+        .clone()
+        .ok_or(InternalError::Unauthorised)?;
+
+    if let Err(form) = request.validate() {
+        let template = NewTemplate {
+            session,
+            quote,
+            form,
+        };
+        return Ok(Html(template.render()?).into_response());
+    }
+
+    request.insert(&pool, quote_id, user.id).await?;
+    cookies.add(Cookie::new("notice", "Comment was successfully added."));
+
+    Ok(Redirect::to(&format!("/quotes/{}", quote_id)).into_response())
+}
+
+pub async fn edit(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((quote_id, comment_id)): Path<(i32, i32)>,
+) -> Result<Html<String>, InternalError> {
+    let quote = Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+    let comment =
+        Comment::fetch_one(&pool, quote_id, comment_id, session.current_user_id()).await?;
+
+    // There must be a user logged in, and they can only edit their own comments.
+    if !session.is_current_user(&comment.user_id) {
+        return Err(InternalError::Unauthorised);
+    }
+
+    let template = EditTemplate {
+        session,
+        quote,
+        comment_id,
+        form: comment.into(),
+    };
+    Ok(Html(template.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "comments/edit.html")]
+struct EditTemplate {
+    session: Session,
+    quote: Quote,
+    comment_id: i32,
+    form: CommentForm,
+}
+
+pub async fn update(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((quote_id, comment_id)): Path<(i32, i32)>,
+    cookies: Cookies,
+    Form(request): Form<CommentRequest>,
+) -> Result<Response, InternalError> {
+    let quote = Quote::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+    let comment =
+        Comment::fetch_one(&pool, quote_id, comment_id, session.current_user_id()).await?;
+
+    // There must be a user logged in, and they can only edit their own comments.
+    if !session.is_current_user(&comment.user_id) {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if let Err(form) = request.validate() {
+        let template = EditTemplate {
+            session,
+            quote,
+            comment_id,
+            form,
+        };
+        return Ok(Html(template.render()?).into_response());
+    }
+
+    Comment::update(&pool, comment_id, request.body.trim()).await?;
+    cookies.add(Cookie::new("notice", "Comment was successfully updated."));
+
+    Ok(Redirect::to(&format!("/quotes/{}/comments/{}", quote_id, comment_id)).into_response())
+}
+
+pub async fn destroy(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((quote_id, comment_id)): Path<(i32, i32)>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    let comment =
+        Comment::fetch_one(&pool, quote_id, comment_id, session.current_user_id()).await?;
+
+    // There must be a user logged in, and they can only delete their own comments.
+    if !session.is_current_user(&comment.user_id) {
+        return Err(InternalError::Unauthorised);
+    }
+
+    Comment::delete(&pool, comment_id).await?;
+    cookies.add(Cookie::new("notice", "Comment was deleted."));
+
+    Ok(Redirect::to(&format!("/quotes/{}", quote_id)))
+}
@@ -1,32 +1,46 @@
-use super::quotes::{self, QuoteForm};
+use super::{
+    comments::CommentForm,
+    quotes::{self, QuoteForm},
+};
 use crate::{
-    atom::quotes::quotes_to_atom,
     config::Config,
     errors::InternalError,
+    feed::{quotes::quotes_to_feed, FeedFormat, FeedPage},
     filters,
-    model::{CommentWithQuote, Context, QuoteWithUsers, User},
-    pagination::{PageOrGap, PaginationState, QueryPage},
-    responses::Atom,
+    model::{
+        violates_unique_index, CommentWithQuote, Context, ContextRole, Invitation, Member,
+        QuoteWithUsers, User,
+    },
+    pagination::{
+        ListPagination, PageOrGap, PaginationState, QueryFeedPage, QueryListPage, QueryPage,
+        LIST_PAGE_SIZE,
+    },
+    responses::{CacheableHtml, Validated},
     session::Session,
 };
 use askama::Template;
 use axum::{
-    extract::{Extension, Path, Query},
-    response::{Html, Redirect},
+    extract::{Extension, Form, Path, Query},
+    http::HeaderMap,
+    response::{Html, IntoResponse, Redirect, Response},
 };
-use paginate::Pages;
+use serde::Deserialize;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
 use tower_cookies::{Cookie, Cookies};
 
 const QUOTES_PER_PAGE: usize = 10;
-const PAGINATION_WINDOW: usize = 2;
+/// The maximum length of a context name, in characters.
+const MAX_NAME_LENGTH: usize = 50;
+/// The error shown when another context has the name. The existing context may be private, so this
+/// doesn't say anything about it.
+const NAME_TAKEN: &str = "That name can't be used. Please choose another.";
 
 pub async fn index(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
 ) -> Result<Html<String>, InternalError> {
-    let contexts = Context::fetch_all(&pool).await?;
+    let contexts = Context::fetch_all(&pool, session.current_user_id()).await?;
 
     let template = IndexTemplate { session, contexts };
     Ok(Html(template.render()?))
@@ -45,27 +59,34 @@ pub async fn show(
     Path(context_id): Path<i32>,
     Query(query): Query<QueryPage>,
 ) -> Result<Html<String>, InternalError> {
-    let context = Context::fetch_one(&pool, context_id).await?;
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
 
-    let quote_count = QuoteWithUsers::count_for_context(&pool, context_id).await?;
-    let pages = Pages::new(quote_count, QUOTES_PER_PAGE);
-    let current_page = pages.with_offset(query.page);
-    let quotes =
-        QuoteWithUsers::fetch_page_for_context(&pool, context_id, &pages, &current_page).await?;
-    let users = User::fetch_all_for_context(&pool, context_id).await?;
-    let comments = CommentWithQuote::fetch_5_for_context(&pool, context_id).await?;
+    let quote_count =
+        QuoteWithUsers::count_for_context(&pool, context_id, session.current_user_id()).await?;
+    let pagination = PaginationState::new(quote_count, QUOTES_PER_PAGE, query.page);
+    let quotes = QuoteWithUsers::fetch_page_for_context(
+        &pool,
+        context_id,
+        &pagination.pages,
+        &pagination.current_page,
+        session.current_user_id(),
+    )
+    .await?;
+    let members = Member::fetch_all_for_context(&pool, context_id).await?;
+    let comments =
+        CommentWithQuote::fetch_5_for_context(&pool, context_id, session.current_user_id()).await?;
+    let current_role = current_role(&pool, &session, context_id).await?;
+    let can_manage = session.admin || current_role.is_some_and(ContextRole::can_manage);
 
     let template = ShowTemplate {
         session,
         context: context.clone(),
         quotes,
-        users,
+        members,
+        current_role,
+        can_manage,
         comments,
-        pagination: PaginationState {
-            pages,
-            current_page,
-            window_size: PAGINATION_WINDOW,
-        },
+        pagination,
         form: QuoteForm {
             context: Some(context),
             ..QuoteForm::default()
@@ -80,7 +101,9 @@ struct ShowTemplate {
     session: Session,
     context: Context,
     quotes: Vec<QuoteWithUsers>,
-    users: Vec<User>,
+    members: Vec<Member>,
+    current_role: Option<ContextRole>,
+    can_manage: bool,
     comments: Vec<CommentWithQuote>,
     pagination: PaginationState,
     form: QuoteForm,
@@ -106,11 +129,114 @@ struct NewTemplate {
     form: ContextForm,
 }
 
+pub async fn create(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    cookies: Cookies,
+    Form(request): Form<ContextRequest>,
+) -> Result<Response, InternalError> {
+    // There must be a user logged in.
+    let user = session
+        .current_user
+        .clone()
+        .ok_or(InternalError::Unauthorised)?;
+
+    if let Err(form) = request.validate(&pool, None).await? {
+        let template = NewTemplate { session, form };
+        return Ok(Html(template.render()?).into_response());
+    }
+
+    let context_id = match Context::create(
+        &pool,
+        request.name.trim(),
+        request.description.trim(),
+        request.private,
+    )
+    .await
+    {
+        Ok(context_id) => context_id,
+        // Another context may have taken the name since it was validated.
+        Err(e) if violates_unique_index(&e, Context::NAME_INDEX) => {
+            let form = request.form_with_errors(&[NAME_TAKEN.to_string()]);
+            let template = NewTemplate { session, form };
+            return Ok(Html(template.render()?).into_response());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    // Whoever creates a context should be its owner.
+    User::join_context(&pool, user.id, context_id, ContextRole::Owner).await?;
+    cookies.add(Cookie::new(
+        "notice",
+        format!(
+            "Quotebook {} was successfully created.",
+            request.name.trim()
+        ),
+    ));
+
+    Ok(Redirect::to(&format!("/contexts/{}", context_id)).into_response())
+}
+
+/// The fields submitted by `contexts/form.html`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ContextRequest {
+    name: String,
+    description: String,
+    /// Whether the context should be private.
+    #[serde(default)]
+    private: bool,
+}
+
+impl ContextRequest {
+    /// Checks that the request is valid, and in particular that the name isn't already used by
+    /// another context than the one being edited, if any.
+    ///
+    /// If there is a problem with the request, returns a form to show the user again with
+    /// appropriate error messages.
+    async fn validate(
+        &self,
+        pool: &Pool<Postgres>,
+        context_id: Option<i32>,
+    ) -> Result<Result<(), ContextForm>, InternalError> {
+        let mut errors = vec![];
+
+        let name = self.name.trim();
+        if name.is_empty() {
+            errors.push("Name can't be blank.".to_string());
+        } else if name.chars().count() > MAX_NAME_LENGTH {
+            errors.push(format!(
+                "Name is too long (maximum is {} characters).",
+                MAX_NAME_LENGTH
+            ));
+        } else if let Some(existing) = Context::fetch_by_name(pool, name).await? {
+            if Some(existing.id) != context_id {
+                errors.push(NAME_TAKEN.to_string());
+            }
+        }
+
+        Ok(if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.form_with_errors(&errors))
+        })
+    }
+
+    /// Returns a form to show the user again with the given error messages.
+    fn form_with_errors(&self, errors: &[String]) -> ContextForm {
+        ContextForm {
+            error_messages: errors.join(" "),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            private: self.private,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 struct ContextForm {
     error_messages: String,
     name: String,
     description: String,
+    private: bool,
 }
 
 impl From<Context> for ContextForm {
@@ -119,27 +245,25 @@ impl From<Context> for ContextForm {
             error_messages: "".to_string(),
             name: context.name,
             description: context.description,
+            private: context.private,
         }
     }
 }
 
 pub async fn edit(
+    Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Path(context_id): Path<i32>,
 ) -> Result<Html<String>, InternalError> {
-    let context = Context::fetch_one(&pool, context_id).await?;
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
 
-    // There must be a user logged in.
-    if !session.logged_in() {
+    // Only owners and admins of the context may edit it.
+    if !can_manage(&pool, &session, context_id).await? {
         return Err(InternalError::Unauthorised);
     }
 
-    let template = EditTemplate {
-        session,
-        form: context.into(),
-        context_id,
-    };
+    let template = EditTemplate::new(&pool, &config, session, context.into(), context_id).await?;
     Ok(Html(template.render()?))
 }
 
@@ -149,6 +273,102 @@ struct EditTemplate {
     session: Session,
     form: ContextForm,
     context_id: i32,
+    members: Vec<Member>,
+    /// Whether the current user may change the roles of other members and manage invitations.
+    can_change_roles: bool,
+    /// Outstanding invitations to the context, along with their URLs.
+    invitations: Vec<(Invitation, String)>,
+}
+
+impl EditTemplate {
+    async fn new(
+        pool: &Pool<Postgres>,
+        config: &Config,
+        session: Session,
+        form: ContextForm,
+        context_id: i32,
+    ) -> Result<Self, InternalError> {
+        let members = Member::fetch_all_for_context(pool, context_id).await?;
+        let can_change_roles = is_owner(pool, &session, context_id).await?;
+        let invitations = if can_change_roles {
+            Invitation::fetch_outstanding_for_context(pool, context_id)
+                .await?
+                .into_iter()
+                .map(|invitation| {
+                    let url = config.absolute_url(&format!("/invitations/{}", invitation.token));
+                    (invitation, url)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        Ok(Self {
+            session,
+            form,
+            context_id,
+            members,
+            can_change_roles,
+            invitations,
+        })
+    }
+
+    /// Returns whether the current user may remove the given member from the context.
+    fn can_remove(&self, member: &Member) -> bool {
+        match member.role {
+            ContextRole::Owner => false,
+            ContextRole::Admin => self.can_change_roles,
+            ContextRole::Member => true,
+        }
+    }
+}
+
+pub async fn update(
+    Extension(config): Extension<Arc<Config>>,
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(context_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<ContextRequest>,
+) -> Result<Response, InternalError> {
+    // Make sure the context exists.
+    Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+
+    // Only owners and admins of the context may edit it.
+    if !can_manage(&pool, &session, context_id).await? {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if let Err(form) = request.validate(&pool, Some(context_id)).await? {
+        let template = EditTemplate::new(&pool, &config, session, form, context_id).await?;
+        return Ok(Html(template.render()?).into_response());
+    }
+
+    if let Err(e) = Context::update(
+        &pool,
+        context_id,
+        request.name.trim(),
+        request.description.trim(),
+        request.private,
+    )
+    .await
+    {
+        // Another context may have taken the name since it was validated.
+        if !violates_unique_index(&e, Context::NAME_INDEX) {
+            return Err(e.into());
+        }
+        let form = request.form_with_errors(&[NAME_TAKEN.to_string()]);
+        let template = EditTemplate::new(&pool, &config, session, form, context_id).await?;
+        return Ok(Html(template.render()?).into_response());
+    }
+    cookies.add(Cookie::new(
+        "notice",
+        format!(
+            "Quotebook {} was successfully updated.",
+            request.name.trim()
+        ),
+    ));
+
+    Ok(Redirect::to(&format!("/contexts/{}", context_id)).into_response())
 }
 
 pub async fn latest(
@@ -156,13 +376,20 @@ pub async fn latest(
     session: Session,
     Path(context_id): Path<i32>,
 ) -> Result<Html<String>, InternalError> {
-    let quote = QuoteWithUsers::fetch_latest_for_context(&pool, context_id).await?;
-    let comments = CommentWithQuote::fetch_all_for_quote(&pool, quote.quote.id).await?;
+    let quote =
+        QuoteWithUsers::fetch_latest_for_context(&pool, context_id, session.current_user_id())
+            .await?;
+    let comments =
+        CommentWithQuote::fetch_all_for_quote(&pool, quote.quote.id, session.current_user_id())
+            .await?;
+    let can_moderate = can_manage(&pool, &session, context_id).await?;
 
     let template = quotes::ShowTemplate {
         session,
         quote,
         comments,
+        form: CommentForm::default(),
+        can_moderate,
     };
     Ok(Html(template.render()?))
 }
@@ -174,9 +401,9 @@ pub async fn join(
     cookies: Cookies,
 ) -> Result<Redirect, InternalError> {
     let current_user = session.current_user.ok_or(InternalError::Unauthorised)?;
-    let context = Context::fetch_one(&pool, context_id).await?;
+    let context = Context::fetch_one(&pool, context_id, Some(current_user.id)).await?;
 
-    User::join_context(&pool, current_user.id, context_id).await?;
+    User::join_context(&pool, current_user.id, context_id, ContextRole::Member).await?;
     cookies.add(Cookie::new(
         "notice",
         format!("You are now a member of {}.", context.name),
@@ -192,7 +419,16 @@ pub async fn leave(
     cookies: Cookies,
 ) -> Result<Redirect, InternalError> {
     let current_user = session.current_user.ok_or(InternalError::Unauthorised)?;
-    let context = Context::fetch_one(&pool, context_id).await?;
+    let context = Context::fetch_one(&pool, context_id, Some(current_user.id)).await?;
+
+    // The owner can't leave, as then nobody would be left to manage the context.
+    if ContextRole::fetch(&pool, context_id, current_user.id).await? == Some(ContextRole::Owner) {
+        cookies.add(Cookie::new(
+            "error",
+            format!("You can't leave {} because you own it.", context.name),
+        ));
+        return Ok(Redirect::to(&format!("/contexts/{}", context_id)));
+    }
 
     User::leave_context(&pool, current_user.id, context_id).await?;
     cookies.add(Cookie::new(
@@ -203,15 +439,162 @@ pub async fn leave(
     Ok(Redirect::to(&format!("/contexts/{}", context_id)))
 }
 
-pub async fn quotes(
+pub async fn remove_member(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((context_id, user_id)): Path<(i32, i32)>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+    let user = User::fetch_one(&pool, user_id).await?;
+    let role = ContextRole::fetch(&pool, context_id, user_id)
+        .await?
+        .ok_or(InternalError::NotFound)?;
+
+    // Owners and admins may remove members, but only owners may remove admins, and nobody may
+    // remove the owner.
+    let current_role = current_role(&pool, &session, context_id).await?;
+    let allowed = match role {
+        ContextRole::Owner => false,
+        ContextRole::Admin => session.admin || current_role == Some(ContextRole::Owner),
+        ContextRole::Member => session.admin || current_role.is_some_and(ContextRole::can_manage),
+    };
+    if !allowed {
+        return Err(InternalError::Unauthorised);
+    }
+
+    User::leave_context(&pool, user_id, context_id).await?;
+    cookies.add(Cookie::new(
+        "notice",
+        format!("{} was removed from {}.", user.fullname, context.name),
+    ));
+
+    Ok(Redirect::to(&format!("/contexts/{}/edit", context_id)))
+}
+
+pub async fn set_role(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((context_id, user_id)): Path<(i32, i32)>,
+    cookies: Cookies,
+    Form(request): Form<RoleRequest>,
+) -> Result<Redirect, InternalError> {
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+    let user = User::fetch_one(&pool, user_id).await?;
+    let role = ContextRole::fetch(&pool, context_id, user_id)
+        .await?
+        .ok_or(InternalError::NotFound)?;
+
+    // Only owners may change roles, and only site admins may make or unmake owners.
+    let allowed = if session.admin {
+        true
+    } else {
+        is_owner(&pool, &session, context_id).await?
+            && role != ContextRole::Owner
+            && request.role != ContextRole::Owner
+    };
+    if !allowed {
+        return Err(InternalError::Unauthorised);
+    }
+
+    // Every context must have an owner, so ownership can only be given to someone else.
+    if role == ContextRole::Owner && request.role != ContextRole::Owner {
+        cookies.add(Cookie::new(
+            "error",
+            format!(
+                "{} owns {}. Make someone else the owner instead.",
+                user.fullname, context.name
+            ),
+        ));
+        return Ok(Redirect::to(&format!("/contexts/{}/edit", context_id)));
+    }
+
+    // Making someone the owner makes the previous owner an admin.
+    Member::set_role(&pool, context_id, user_id, request.role).await?;
+    cookies.add(Cookie::new(
+        "notice",
+        format!(
+            "{} is now {} of {}.",
+            user.fullname,
+            match request.role {
+                ContextRole::Owner => "the owner",
+                ContextRole::Admin => "an admin",
+                ContextRole::Member => "a member",
+            },
+            context.name
+        ),
+    ));
+
+    Ok(Redirect::to(&format!("/contexts/{}/edit", context_id)))
+}
+
+/// The fields submitted by the role form in `contexts/edit.html`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RoleRequest {
+    role: ContextRole,
+}
+
+/// Returns the role of the current user in the given context, or `None` if they aren't logged in
+/// or aren't a member.
+async fn current_role(
+    pool: &Pool<Postgres>,
+    session: &Session,
+    context_id: i32,
+) -> Result<Option<ContextRole>, InternalError> {
+    Ok(match &session.current_user {
+        Some(user) => ContextRole::fetch(pool, context_id, user.id).await?,
+        None => None,
+    })
+}
+
+/// Returns whether the current user may change roles and manage invitations in the given context,
+/// either because they own it or because they are a site admin.
+pub async fn is_owner(
+    pool: &Pool<Postgres>,
+    session: &Session,
+    context_id: i32,
+) -> Result<bool, InternalError> {
+    Ok(session.admin || current_role(pool, session, context_id).await? == Some(ContextRole::Owner))
+}
+
+/// Returns whether the current user may edit the given context and moderate its members and
+/// quotes, either because they are an owner or admin of it or because they are a site admin.
+pub async fn can_manage(
+    pool: &Pool<Postgres>,
+    session: &Session,
+    context_id: i32,
+) -> Result<bool, InternalError> {
+    Ok(session.admin
+        || current_role(pool, session, context_id)
+            .await?
+            .is_some_and(ContextRole::can_manage))
+}
+
+pub async fn hidden(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Path(context_id): Path<i32>,
 ) -> Result<Html<String>, InternalError> {
-    let context = Context::fetch_one(&pool, context_id).await?;
-    let quotes = QuoteWithUsers::fetch_all_for_context(&pool, context_id).await?;
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
 
-    let template = QuotesTemplate {
+    // Moderators see all hidden quotes, anyone else only those of which they are the quotee.
+    let current_user_id = session
+        .current_user_id()
+        .ok_or(InternalError::Unauthorised)?;
+    let quotee_id = if can_manage(&pool, &session, context_id).await? {
+        None
+    } else {
+        Some(current_user_id)
+    };
+    let quotes = QuoteWithUsers::fetch_hidden_for_context(
+        &pool,
+        context_id,
+        quotee_id,
+        session.current_user_id(),
+    )
+    .await?;
+
+    let template = HiddenTemplate {
         session,
         context,
         quotes,
@@ -219,23 +602,103 @@ pub async fn quotes(
     Ok(Html(template.render()?))
 }
 
+#[derive(Template)]
+#[template(path = "contexts/hidden.html")]
+struct HiddenTemplate {
+    session: Session,
+    context: Context,
+    quotes: Vec<QuoteWithUsers>,
+}
+
+pub async fn quotes(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(context_id): Path<i32>,
+    Query(query): Query<QueryListPage>,
+) -> Result<CacheableHtml, InternalError> {
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after_for_context(
+            &pool,
+            context_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count =
+            QuoteWithUsers::count_for_context(&pool, context_id, session.current_user_id()).await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page_for_context(
+            &pool,
+            context_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
+
+    let template = QuotesTemplate {
+        session,
+        context,
+        quotes,
+        pagination,
+    };
+    Ok(CacheableHtml(template.render()?))
+}
+
 #[derive(Template)]
 #[template(path = "contexts/quotes.html")]
 struct QuotesTemplate {
     session: Session,
     context: Context,
     quotes: Vec<QuoteWithUsers>,
+    pagination: ListPagination,
 }
 
-pub async fn quotes_atom(
+pub async fn quotes_feed<F: FeedFormat>(
     Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
     Path(context_id): Path<i32>,
-) -> Result<Atom, InternalError> {
-    let context = Context::fetch_one(&pool, context_id).await?;
-    let quotes = QuoteWithUsers::fetch_all_for_context(&pool, context_id).await?;
+    Query(query): Query<QueryFeedPage>,
+    headers: HeaderMap,
+) -> Result<Validated<F>, InternalError> {
+    let context = Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+    let total =
+        QuoteWithUsers::count_for_context(&pool, context_id, session.current_user_id()).await?;
+    let mut page = FeedPage::new(&query, total, config.feed_entries)?;
+    let quotes = match page.archive_entries(total, config.feed_entries) {
+        Some((pages, archive)) => {
+            QuoteWithUsers::fetch_page_for_context(
+                &pool,
+                context_id,
+                &pages,
+                &archive,
+                session.current_user_id(),
+            )
+            .await?
+        }
+        None => {
+            let quotes = QuoteWithUsers::fetch_after_for_context(
+                &pool,
+                context_id,
+                query.after.as_ref(),
+                config.feed_entries + 1,
+                session.current_user_id(),
+            )
+            .await?;
+            let (quotes, pagination) = ListPagination::after_cursor(quotes, config.feed_entries);
+            page.next = pagination.next;
+            quotes
+        }
+    };
     let title = format!("theQuotebook: {} quotes", context.name);
     let path = format!("/contexts/{}/quotes", context_id);
 
-    Ok(Atom(quotes_to_atom(quotes, title, &path, &config)?))
+    quotes_to_feed(quotes, title, &path, &page, &config, &headers)
 }
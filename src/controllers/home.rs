@@ -1,45 +1,57 @@
 use crate::{
-    atom::comments::comments_to_atom,
     config::Config,
     errors::InternalError,
+    feed::{comments::comments_to_feed, FeedFormat, FeedPage},
     filters,
     model::{CommentWithQuote, CommentWithQuotee, Context, QuoteWithUsers},
-    pagination::{PageOrGap, PaginationState, QueryPage},
-    responses::Atom,
+    pagination::{
+        ListPagination, PageOrGap, PaginationState, QueryFeedPage, QueryListPage, QueryPage,
+        LIST_PAGE_SIZE,
+    },
+    responses::{CacheableHtml, Validated},
     session::Session,
 };
 use askama::Template;
 use axum::{
     extract::{Extension, Query},
-    response::Html,
+    http::HeaderMap,
 };
-use paginate::Pages;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
 
 const QUOTES_PER_PAGE: usize = 5;
-const PAGINATION_WINDOW: usize = 2;
 
 pub async fn index(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Query(query): Query<QueryPage>,
-) -> Result<Html<String>, InternalError> {
-    let top_contexts = Context::fetch_top_5(&pool).await?;
+) -> Result<CacheableHtml, InternalError> {
+    let top_contexts = Context::fetch_top_5(&pool, session.current_user_id()).await?;
 
     let template = if let Some(current_user) = &session.current_user {
-        let quote_count = QuoteWithUsers::count_for_user_contexts(&pool, current_user.id).await?;
-        let pages = Pages::new(quote_count, QUOTES_PER_PAGE);
-        let current_page = pages.with_offset(query.page);
+        let quote_count = QuoteWithUsers::count_for_user_contexts(
+            &pool,
+            current_user.id,
+            session.current_user_id(),
+        )
+        .await?;
+        let pagination = PaginationState::new(quote_count, QUOTES_PER_PAGE, query.page);
         let quotes = QuoteWithUsers::fetch_page_for_user_contexts(
             &pool,
             current_user.id,
-            &pages,
-            &current_page,
+            &pagination.pages,
+            &pagination.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        let current_user_contexts =
+            Context::fetch_for_user(&pool, current_user.id, session.current_user_id()).await?;
+        let comments = CommentWithQuote::fetch_5_for_user_contexts(
+            &pool,
+            current_user.id,
+            session.current_user_id(),
         )
         .await?;
-        let current_user_contexts = Context::fetch_for_user(&pool, current_user.id).await?;
-        let comments = CommentWithQuote::fetch_5_for_user_contexts(&pool, current_user.id).await?;
 
         IndexTemplate {
             session,
@@ -47,17 +59,18 @@ pub async fn index(
             top_contexts,
             current_user_contexts,
             comments,
-            pagination: PaginationState {
-                pages,
-                current_page,
-                window_size: PAGINATION_WINDOW,
-            },
+            pagination,
         }
     } else {
-        let quote_count = QuoteWithUsers::count(&pool).await?;
-        let pages = Pages::new(quote_count, QUOTES_PER_PAGE);
-        let current_page = pages.with_offset(query.page);
-        let quotes = QuoteWithUsers::fetch_page(&pool, &pages, &current_page).await?;
+        let quote_count = QuoteWithUsers::count(&pool, session.current_user_id()).await?;
+        let pagination = PaginationState::new(quote_count, QUOTES_PER_PAGE, query.page);
+        let quotes = QuoteWithUsers::fetch_page(
+            &pool,
+            &pagination.pages,
+            &pagination.current_page,
+            session.current_user_id(),
+        )
+        .await?;
 
         IndexTemplate {
             session,
@@ -65,14 +78,10 @@ pub async fn index(
             top_contexts,
             current_user_contexts: vec![],
             comments: vec![],
-            pagination: PaginationState {
-                pages,
-                current_page,
-                window_size: PAGINATION_WINDOW,
-            },
+            pagination,
         }
     };
-    Ok(Html(template.render()?))
+    Ok(CacheableHtml(template.render()?))
 }
 
 #[derive(Template)]
@@ -89,11 +98,36 @@ struct IndexTemplate {
 pub async fn comments(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
-) -> Result<Html<String>, InternalError> {
-    let comments = CommentWithQuotee::fetch_all(&pool).await?;
+    Query(query): Query<QueryListPage>,
+) -> Result<CacheableHtml, InternalError> {
+    let (comments, pagination) = if let Some(after) = &query.after {
+        let comments = CommentWithQuotee::fetch_after(
+            &pool,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(comments, LIST_PAGE_SIZE)
+    } else {
+        let comment_count = CommentWithQuotee::count(&pool, session.current_user_id()).await?;
+        let numbered = PaginationState::new(comment_count, LIST_PAGE_SIZE, query.page);
+        let comments = CommentWithQuotee::fetch_page(
+            &pool,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(comments, numbered)
+    };
 
-    let template = CommentsTemplate { session, comments };
-    Ok(Html(template.render()?))
+    let template = CommentsTemplate {
+        session,
+        comments,
+        pagination,
+    };
+    Ok(CacheableHtml(template.render()?))
 }
 
 #[derive(Template)]
@@ -101,15 +135,39 @@ pub async fn comments(
 struct CommentsTemplate {
     session: Session,
     comments: Vec<CommentWithQuotee>,
+    pagination: ListPagination,
 }
 
-pub async fn comments_atom(
+pub async fn comments_feed<F: FeedFormat>(
     Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
-) -> Result<Atom, InternalError> {
-    let comments = CommentWithQuotee::fetch_all(&pool).await?;
+    session: Session,
+    Query(query): Query<QueryFeedPage>,
+    headers: HeaderMap,
+) -> Result<Validated<F>, InternalError> {
+    let total = CommentWithQuotee::count(&pool, session.current_user_id()).await?;
+    let mut page = FeedPage::new(&query, total, config.feed_entries)?;
+    let comments = match page.archive_entries(total, config.feed_entries) {
+        Some((pages, archive)) => {
+            CommentWithQuotee::fetch_page(&pool, &pages, &archive, session.current_user_id())
+                .await?
+        }
+        None => {
+            let comments = CommentWithQuotee::fetch_after(
+                &pool,
+                query.after.as_ref(),
+                config.feed_entries + 1,
+                session.current_user_id(),
+            )
+            .await?;
+            let (comments, pagination) =
+                ListPagination::after_cursor(comments, config.feed_entries);
+            page.next = pagination.next;
+            comments
+        }
+    };
     let title = "theQuotebook: All comments".to_owned();
     let path = "/comments";
 
-    Ok(Atom(comments_to_atom(comments, title, path, &config)?))
+    comments_to_feed(comments, title, path, &page, &config, &headers)
 }
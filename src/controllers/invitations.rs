@@ -0,0 +1,124 @@
+use super::contexts;
+use crate::{
+    errors::InternalError,
+    model::{Context, Invitation},
+    session::Session,
+};
+use axum::{
+    extract::{Extension, Form, Path},
+    response::Redirect,
+};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+use tower_cookies::{Cookie, Cookies};
+
+/// The longest an invitation may be valid for, in days.
+const MAX_VALID_DAYS: i32 = 90;
+
+pub async fn create(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(context_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<InvitationRequest>,
+) -> Result<Redirect, InternalError> {
+    // Make sure the context exists.
+    Context::fetch_one(&pool, context_id, session.current_user_id()).await?;
+
+    // Only owners may invite people.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+    if !contexts::is_owner(&pool, &session, context_id).await? {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if !(1..=MAX_VALID_DAYS).contains(&request.valid_days) {
+        cookies.add(Cookie::new(
+            "error",
+            format!(
+                "Invitations must be valid for between 1 and {} days.",
+                MAX_VALID_DAYS
+            ),
+        ));
+    } else {
+        Invitation::create(
+            &pool,
+            context_id,
+            user.id,
+            request.single_use,
+            request.valid_days,
+        )
+        .await?;
+        cookies.add(Cookie::new(
+            "notice",
+            "Invitation was successfully created.",
+        ));
+    }
+
+    Ok(Redirect::to(&format!("/contexts/{}/edit", context_id)))
+}
+
+/// The fields submitted by the invitation form in `contexts/edit.html`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct InvitationRequest {
+    valid_days: i32,
+    #[serde(default)]
+    single_use: bool,
+}
+
+pub async fn destroy(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path((context_id, invitation_id)): Path<(i32, i32)>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    // Only owners may revoke invitations.
+    if !contexts::is_owner(&pool, &session, context_id).await? {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if !Invitation::delete(&pool, context_id, invitation_id).await? {
+        return Err(InternalError::NotFound);
+    }
+    cookies.add(Cookie::new("notice", "Invitation was revoked."));
+
+    Ok(Redirect::to(&format!("/contexts/{}/edit", context_id)))
+}
+
+pub async fn show(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(token): Path<String>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    // Tokens are always alphanumeric, so anything else can't be a valid invitation, and mustn't
+    // be passed on in the redirect below.
+    if !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(InternalError::NotFound);
+    }
+
+    // Send people who aren't logged in back here once they are.
+    let Some(user) = &session.current_user else {
+        return Ok(Redirect::to(&format!(
+            "/login?redirect=/invitations/{}",
+            token
+        )));
+    };
+
+    let Some(context_id) = Invitation::accept(&pool, &token, user.id).await? else {
+        cookies.add(Cookie::new(
+            "error",
+            "That invitation has expired or been revoked.",
+        ));
+        return Ok(Redirect::to("/"));
+    };
+    let context = Context::fetch_one(&pool, context_id, Some(user.id)).await?;
+    cookies.add(Cookie::new(
+        "notice",
+        format!("You are now a member of {}.", context.name),
+    ));
+
+    Ok(Redirect::to(&format!("/contexts/{}", context_id)))
+}
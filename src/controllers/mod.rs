@@ -1,6 +1,12 @@
+pub mod admin;
+pub mod api;
+pub mod api_tokens;
 pub mod comments;
 pub mod contexts;
 pub mod home;
+pub mod invitations;
+pub mod notifications;
 pub mod quotes;
+pub mod search;
 pub mod sessions;
 pub mod users;
@@ -0,0 +1,39 @@
+use crate::{
+    errors::InternalError,
+    filters,
+    model::{Notification, QuoteWithUsers},
+    session::Session,
+};
+use askama::Template;
+use axum::{extract::Extension, response::Html};
+use sqlx::{Pool, Postgres};
+
+pub async fn index(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+) -> Result<Html<String>, InternalError> {
+    // There must be a user logged in.
+    let user_id = session
+        .current_user_id()
+        .ok_or(InternalError::Unauthorised)?;
+
+    let pending_quotes = QuoteWithUsers::fetch_pending_for_quotee(&pool, user_id).await?;
+    let notifications = Notification::fetch_recent_for_user(&pool, user_id).await?;
+    // They have now been seen, though they are still shown as unread this time.
+    Notification::mark_all_read(&pool, user_id).await?;
+
+    let template = IndexTemplate {
+        session,
+        pending_quotes,
+        notifications,
+    };
+    Ok(Html(template.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "notifications/index.html")]
+struct IndexTemplate {
+    session: Session,
+    pending_quotes: Vec<QuoteWithUsers>,
+    notifications: Vec<Notification>,
+}
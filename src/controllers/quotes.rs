@@ -1,28 +1,61 @@
+use super::{comments::CommentForm, contexts};
 use crate::{
-    atom::quotes::quotes_to_atom,
     config::Config,
     errors::InternalError,
+    feed::{quotes::quotes_to_feed, FeedFormat, FeedPage},
     filters::{self},
-    model::{CommentWithQuote, Context, QuoteWithUsers},
-    responses::Atom,
+    model::{CommentWithQuote, Context, ContextRole, Notification, Quote, QuoteWithUsers, User},
+    pagination::{
+        ListPagination, PageOrGap, PaginationState, QueryFeedPage, QueryListPage, LIST_PAGE_SIZE,
+    },
+    responses::{CacheableHtml, Validated},
     session::Session,
 };
 use askama::Template;
 use axum::{
-    extract::{Extension, Path},
-    response::Html,
+    extract::{Extension, Form, Path, Query},
+    http::HeaderMap,
+    response::{Html, IntoResponse, Redirect, Response},
 };
+use serde::Deserialize;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
+use tower_cookies::{Cookie, Cookies};
+use utoipa::ToSchema;
 
 pub async fn index(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
-) -> Result<Html<String>, InternalError> {
-    let quotes = QuoteWithUsers::fetch_all(&pool).await?;
+    Query(query): Query<QueryListPage>,
+) -> Result<CacheableHtml, InternalError> {
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after(
+            &pool,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count = QuoteWithUsers::count(&pool, session.current_user_id()).await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page(
+            &pool,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
 
-    let template = IndexTemplate { session, quotes };
-    Ok(Html(template.render()?))
+    let template = IndexTemplate {
+        session,
+        quotes,
+        pagination,
+    };
+    Ok(CacheableHtml(template.render()?))
 }
 
 #[derive(Template)]
@@ -30,16 +63,38 @@ pub async fn index(
 struct IndexTemplate {
     session: Session,
     quotes: Vec<QuoteWithUsers>,
+    pagination: ListPagination,
 }
 
-pub async fn index_atom(
+pub async fn index_feed<F: FeedFormat>(
     Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
-) -> Result<Atom, InternalError> {
-    let quotes = QuoteWithUsers::fetch_all(&pool).await?;
+    session: Session,
+    Query(query): Query<QueryFeedPage>,
+    headers: HeaderMap,
+) -> Result<Validated<F>, InternalError> {
+    let total = QuoteWithUsers::count(&pool, session.current_user_id()).await?;
+    let mut page = FeedPage::new(&query, total, config.feed_entries)?;
+    let quotes = match page.archive_entries(total, config.feed_entries) {
+        Some((pages, archive)) => {
+            QuoteWithUsers::fetch_page(&pool, &pages, &archive, session.current_user_id()).await?
+        }
+        None => {
+            let quotes = QuoteWithUsers::fetch_after(
+                &pool,
+                query.after.as_ref(),
+                config.feed_entries + 1,
+                session.current_user_id(),
+            )
+            .await?;
+            let (quotes, pagination) = ListPagination::after_cursor(quotes, config.feed_entries);
+            page.next = pagination.next;
+            quotes
+        }
+    };
     let title = "theQuotebook: All quotes".to_string();
 
-    Ok(Atom(quotes_to_atom(quotes, title, "/quotes", &config)?))
+    quotes_to_feed(quotes, title, "/quotes", &page, &config, &headers)
 }
 
 pub async fn show(
@@ -47,13 +102,17 @@ pub async fn show(
     session: Session,
     Path(quote_id): Path<i32>,
 ) -> Result<Html<String>, InternalError> {
-    let quote = QuoteWithUsers::fetch_one(&pool, quote_id).await?;
-    let comments = CommentWithQuote::fetch_all_for_quote(&pool, quote_id).await?;
+    let quote = QuoteWithUsers::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+    let comments =
+        CommentWithQuote::fetch_all_for_quote(&pool, quote_id, session.current_user_id()).await?;
+    let can_moderate = contexts::can_manage(&pool, &session, quote.context.id).await?;
 
     let template = ShowTemplate {
         session,
         quote,
         comments,
+        form: CommentForm::default(),
+        can_moderate,
     };
     Ok(Html(template.render()?))
 }
@@ -64,6 +123,9 @@ pub struct ShowTemplate {
     pub session: Session,
     pub quote: QuoteWithUsers,
     pub comments: Vec<CommentWithQuote>,
+    pub form: CommentForm,
+    /// Whether the current user may moderate quotes in the quote's context.
+    pub can_moderate: bool,
 }
 
 pub async fn new(session: Session) -> Result<Html<String>, InternalError> {
@@ -86,10 +148,167 @@ struct NewTemplate {
     form: QuoteForm,
 }
 
+pub async fn create(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    cookies: Cookies,
+    Form(request): Form<QuoteRequest>,
+) -> Result<Response, InternalError> {
+    // There must be a user logged in.
+    let user = session
+        .current_user
+        .clone()
+        .ok_or(InternalError::Unauthorised)?;
+
+    let (context, quotee) = match request.validate(&pool, user.id).await? {
+        Ok(valid) => valid,
+        Err(form) => {
+            let template = NewTemplate { session, form };
+            return Ok(Html(template.render()?).into_response());
+        }
+    };
+
+    let (quote_id, pending) = request.insert(&pool, user.id, &context, &quotee).await?;
+    if pending {
+        cookies.add(Cookie::new(
+            "notice",
+            format!(
+                "Quote was successfully added. It will be shown once {} approves it.",
+                quotee.fullname
+            ),
+        ));
+        return Ok(Redirect::to(&format!("/contexts/{}", context.id)).into_response());
+    }
+    cookies.add(Cookie::new("notice", "Quote was successfully added."));
+
+    Ok(Redirect::to(&format!("/quotes/{}", quote_id)).into_response())
+}
+
+/// The fields submitted by `quotes/form.html`, or to the JSON API.
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct QuoteRequest {
+    quote_text: String,
+    quotee_name: String,
+    context_name: String,
+    /// The ID of the user chosen from the list of possible quotee matches, if any.
+    quotee_id: Option<i32>,
+    /// Whether to create a new placeholder user if nobody called `quotee_name` exists.
+    #[serde(default)]
+    create_quotee: bool,
+}
+
+impl QuoteRequest {
+    /// Checks that the request is valid and looks up the context and quotee it refers to.
+    ///
+    /// If there is a problem with the request, returns a form to show the user again with
+    /// appropriate error messages, and a list of possible matches if the quotee couldn't be found.
+    pub async fn validate(
+        &self,
+        pool: &Pool<Postgres>,
+        quoter_id: i32,
+    ) -> Result<Result<(Context, User), QuoteForm>, InternalError> {
+        let mut errors = vec![];
+
+        if self.quote_text.trim().is_empty() {
+            errors.push("Quote text can't be blank.".to_string());
+        }
+
+        let context_name = self.context_name.trim();
+        let context = if context_name.is_empty() {
+            errors.push("You must choose a quotebook for the quote.".to_string());
+            None
+        } else {
+            let context = match Context::fetch_by_name(pool, context_name).await? {
+                // Only members may see, and so add quotes to, a private context.
+                Some(context) if context.private => ContextRole::fetch(pool, context.id, quoter_id)
+                    .await?
+                    .map(|_| context),
+                context => context,
+            };
+            if context.is_none() {
+                errors.push(format!("There is no quotebook called '{}'.", context_name));
+            }
+            context
+        };
+
+        let quotee_name = self.quotee_name.trim();
+        let mut possible_quotee_matches = None;
+        let quotee = if let Some(quotee_id) = self.quotee_id {
+            match User::fetch_one(pool, quotee_id).await {
+                Ok(quotee) => Some(quotee),
+                Err(InternalError::NotFound) => {
+                    errors.push("The chosen person no longer exists.".to_string());
+                    None
+                }
+                Err(e) => return Err(e),
+            }
+        } else if quotee_name.is_empty() {
+            errors.push("You must say who said the quote.".to_string());
+            None
+        } else if let Some(quotee) = User::fetch_by_name(pool, quotee_name).await? {
+            Some(quotee)
+        } else if self.create_quotee {
+            // Only create the new person once we know that the quote is otherwise valid, so we
+            // don't leave stray users behind.
+            if errors.is_empty() {
+                Some(User::create_placeholder(pool, quotee_name).await?)
+            } else {
+                None
+            }
+        } else {
+            possible_quotee_matches = Some(
+                User::fetch_possible_matches(
+                    pool,
+                    quotee_name,
+                    context.as_ref().map(|context| context.id),
+                )
+                .await?,
+            );
+            None
+        };
+
+        Ok(match (context, quotee) {
+            (Some(context), Some(quotee)) if errors.is_empty() => Ok((context, quotee)),
+            _ => Err(QuoteForm {
+                error_messages: errors.join(" "),
+                possible_quotee_matches,
+                quotee: self.quotee_name.clone(),
+                context_name: self.context_name.clone(),
+                context: None,
+                quote_text: self.quote_text.clone(),
+            }),
+        })
+    }
+
+    /// Adds the quote which was validated as being in the given context and about the given
+    /// quotee, holding it back if the quotee wants to approve it first.
+    ///
+    /// Returns the ID of the new quote, and whether it is waiting for approval.
+    pub async fn insert(
+        &self,
+        pool: &Pool<Postgres>,
+        quoter_id: i32,
+        context: &Context,
+        quotee: &User,
+    ) -> Result<(i32, bool), InternalError> {
+        let pending = needs_approval(pool, quoter_id, quotee.id).await?;
+        let quote_id = Quote::create(
+            pool,
+            self.quote_text.trim(),
+            context.id,
+            quoter_id,
+            quotee.id,
+            pending,
+        )
+        .await?;
+        Ok((quote_id, pending))
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct QuoteForm {
     pub error_messages: String,
-    pub possible_quotee_matches: Option<Vec<String>>,
+    pub possible_quotee_matches: Option<Vec<User>>,
     pub quotee: String,
     pub context_name: String,
     pub context: Option<Context>,
@@ -114,16 +333,12 @@ pub async fn edit(
     session: Session,
     Path(quote_id): Path<i32>,
 ) -> Result<Html<String>, InternalError> {
-    let quote = QuoteWithUsers::fetch_one(&pool, quote_id).await?;
-
     // There must be a user logged in, and they can only edit their own quotes.
     let user = session
         .current_user
         .clone()
         .ok_or(InternalError::Unauthorised)?;
-    if user.id != quote.quoter.id {
-        return Err(InternalError::Unauthorised);
-    }
+    let quote = QuoteWithUsers::fetch_one_for_quoter(&pool, quote_id, user.id).await?;
 
     let template = EditTemplate {
         session,
@@ -140,3 +355,263 @@ struct EditTemplate {
     form: QuoteForm,
     quote_id: i32,
 }
+
+pub async fn update(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<QuoteRequest>,
+) -> Result<Response, InternalError> {
+    // There must be a user logged in, and they can only edit their own quotes.
+    let user = session
+        .current_user
+        .clone()
+        .ok_or(InternalError::Unauthorised)?;
+    let quote = QuoteWithUsers::fetch_one_for_quoter(&pool, quote_id, user.id).await?;
+
+    let (context, quotee) = match request.validate(&pool, user.id).await? {
+        Ok(valid) => valid,
+        Err(form) => {
+            let template = EditTemplate {
+                session,
+                form: QuoteForm {
+                    context: Some(quote.context),
+                    ..form
+                },
+                quote_id,
+            };
+            return Ok(Html(template.render()?).into_response());
+        }
+    };
+
+    Quote::update(
+        &pool,
+        quote_id,
+        request.quote_text.trim(),
+        context.id,
+        quotee.id,
+    )
+    .await?;
+    // A changed quote needs its quotee's approval again, if they want to give it. If they don't,
+    // a quote which was waiting for the previous quotee's approval can be shown.
+    let changed =
+        quotee.id != quote.quotee.id || request.quote_text.trim() != quote.quote.quote_text;
+    let pending = if needs_approval(&pool, user.id, quotee.id).await? {
+        if changed {
+            Quote::hold_for_approval(&pool, quote_id).await?;
+        }
+        changed || quote.quote.pending
+    } else {
+        Quote::clear_pending(&pool, quote_id).await?;
+        false
+    };
+    if pending {
+        cookies.add(Cookie::new(
+            "notice",
+            format!(
+                "Quote was successfully updated. It will be shown once {} approves it.",
+                quotee.fullname
+            ),
+        ));
+        return Ok(Redirect::to(&format!("/contexts/{}", context.id)).into_response());
+    }
+    cookies.add(Cookie::new("notice", "Quote was successfully updated."));
+
+    Ok(Redirect::to(&format!("/quotes/{}", quote_id)).into_response())
+}
+
+pub async fn destroy(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    let quote =
+        Quote::fetch_one_including_hidden(&pool, quote_id, session.current_user_id()).await?;
+
+    // There must be a user logged in, and they can only delete their own quotes unless they
+    // moderate the context.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+    if user.id != quote.quoter_id
+        && !contexts::can_manage(&pool, &session, quote.context_id).await?
+    {
+        return Err(InternalError::Unauthorised);
+    }
+
+    Quote::delete(&pool, quote_id).await?;
+    cookies.add(Cookie::new("notice", "Quote was deleted."));
+
+    Ok(Redirect::to(&format!("/contexts/{}", quote.context_id)))
+}
+
+pub async fn hide(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    let quote = check_can_hide(&pool, &session, quote_id, false).await?;
+    let by_quotee = session.is_current_user(&quote.quotee_id);
+
+    // A quotee hiding a quote which a moderator already hid still takes it out of the moderator's
+    // hands.
+    if !quote.hidden || (by_quotee && !quote.hidden_by_quotee) {
+        Quote::set_hidden(&pool, quote_id, true, by_quotee).await?;
+        // Let the quoter know if someone else hid their quote.
+        if let Some(user) = &session.current_user {
+            if user.id != quote.quoter_id {
+                Notification::create(
+                    &pool,
+                    quote.quoter_id,
+                    &format!("{} hid your quote “{}”.", user.fullname, quote.quote_text),
+                    None,
+                )
+                .await?;
+            }
+        }
+    }
+    cookies.add(Cookie::new("notice", "Quote was hidden."));
+
+    Ok(Redirect::to(&format!("/contexts/{}", quote.context_id)))
+}
+
+pub async fn unhide(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    let quote = check_can_hide(&pool, &session, quote_id, true).await?;
+    // Only the quotee may decide whether to show a quote waiting for their approval, and they do
+    // that by approving it.
+    if quote.pending {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if quote.hidden {
+        Quote::set_hidden(&pool, quote_id, false, false).await?;
+    }
+    cookies.add(Cookie::new("notice", "Quote is no longer hidden."));
+
+    Ok(Redirect::to(&format!("/quotes/{}", quote_id)))
+}
+
+/// Fetches the given quote, hidden or not, if the current user may hide or unhide it: that is, if
+/// they are its quotee or moderate its context. Only the quotee may unhide a quote which they hid
+/// or rejected, though.
+async fn check_can_hide(
+    pool: &Pool<Postgres>,
+    session: &Session,
+    quote_id: i32,
+    unhiding: bool,
+) -> Result<Quote, InternalError> {
+    let quote =
+        Quote::fetch_one_including_hidden(pool, quote_id, session.current_user_id()).await?;
+    if session.is_current_user(&quote.quotee_id)
+        || (!(unhiding && quote.hidden_by_quotee)
+            && contexts::can_manage(pool, session, quote.context_id).await?)
+    {
+        Ok(quote)
+    } else {
+        Err(InternalError::Unauthorised)
+    }
+}
+
+/// Returns whether a quote by the given quoter about the given quotee must be approved by the
+/// quotee before it is shown. Nobody needs to approve quotes of themselves.
+async fn needs_approval(
+    pool: &Pool<Postgres>,
+    quoter_id: i32,
+    quotee_id: i32,
+) -> Result<bool, InternalError> {
+    Ok(quoter_id != quotee_id && User::quotes_need_approval(pool, quotee_id).await?)
+}
+
+pub async fn request_removal(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    let quote = QuoteWithUsers::fetch_one(&pool, quote_id, session.current_user_id()).await?;
+
+    // Only the quotee may ask for a quote to be removed.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+    if user.id != quote.quotee.id {
+        return Err(InternalError::Unauthorised);
+    }
+
+    Notification::create(
+        &pool,
+        quote.quoter.id,
+        &format!(
+            "{} has asked you to remove your quote of them, “{}”.",
+            user.fullname, quote.quote.quote_text
+        ),
+        Some(&format!("/quotes/{}", quote_id)),
+    )
+    .await?;
+    cookies.add(Cookie::new(
+        "notice",
+        format!("We've asked {} to remove the quote.", quote.quoter.fullname),
+    ));
+
+    Ok(Redirect::to(&format!("/quotes/{}", quote_id)))
+}
+
+pub async fn approve(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    // Only the quotee may approve a quote.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+
+    Quote::set_approved(&pool, quote_id, user.id, true)
+        .await?
+        .ok_or(InternalError::NotFound)?;
+    cookies.add(Cookie::new("notice", "Quote was approved."));
+
+    Ok(Redirect::to("/notifications"))
+}
+
+pub async fn reject(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(quote_id): Path<i32>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    // Only the quotee may reject a quote.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+
+    let quote = Quote::set_approved(&pool, quote_id, user.id, false)
+        .await?
+        .ok_or(InternalError::NotFound)?;
+    Notification::create(
+        &pool,
+        quote.quoter_id,
+        &format!(
+            "{} chose not to have your quote “{}” shown.",
+            user.fullname, quote.quote_text
+        ),
+        None,
+    )
+    .await?;
+    cookies.add(Cookie::new("notice", "Quote was rejected."));
+
+    Ok(Redirect::to("/notifications"))
+}
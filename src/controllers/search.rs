@@ -0,0 +1,192 @@
+use crate::{
+    errors::InternalError,
+    filters,
+    model::{Context, QuoteSearch, QuoteWithUsers, User},
+    pagination::{PageOrGap, PaginationState, LIST_PAGE_SIZE},
+    session::Session,
+};
+use askama::{filters::urlencode, Template};
+use axum::{
+    extract::{Extension, Query},
+    response::Html,
+};
+use serde::{de, Deserialize, Deserializer};
+use sqlx::{types::chrono::NaiveDate, Pool, Postgres};
+use std::{fmt::Display, str::FromStr};
+use utoipa::IntoParams;
+
+pub async fn index(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Query(query): Query<SearchQuery>,
+) -> Result<Html<String>, InternalError> {
+    let contexts = Context::fetch_all(&pool, session.current_user_id()).await?;
+    let users = User::fetch_all(&pool).await?;
+
+    // Don't search until there is something to search for.
+    let (quotes, pagination) = if let Some(search) = query.search() {
+        let quote_count =
+            QuoteWithUsers::count_matching(&pool, &search, session.current_user_id()).await?;
+        let pagination = PaginationState {
+            other_params: query.params_without_page()?,
+            ..PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page)
+        };
+        let quotes = QuoteWithUsers::fetch_page_matching(
+            &pool,
+            &search,
+            &pagination.pages,
+            &pagination.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        (quotes, Some(pagination))
+    } else {
+        (vec![], None)
+    };
+
+    let template = IndexTemplate {
+        session,
+        query,
+        contexts,
+        users,
+        quotes,
+        pagination,
+    };
+    Ok(Html(template.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "search/index.html")]
+struct IndexTemplate {
+    session: Session,
+    query: SearchQuery,
+    contexts: Vec<Context>,
+    users: Vec<User>,
+    quotes: Vec<QuoteWithUsers>,
+    /// This is `None` if nothing has been searched for yet.
+    pagination: Option<PaginationState>,
+}
+
+/// The query parameters of a search, from the form in `search/index.html` or the JSON API.
+#[derive(Clone, Debug, Default, Deserialize, Eq, IntoParams, PartialEq)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Words to look for in quotes and their comments. Phrases may be quoted, alternatives joined
+    /// with `or`, and words excluded with `-`.
+    #[serde(default)]
+    pub q: String,
+    /// Only include quotes in this context.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub context_id: Option<i32>,
+    /// Only include quotes of this quotee.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub quotee_id: Option<i32>,
+    /// Only include quotes added on or after this date.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub from: Option<NaiveDate>,
+    /// Only include quotes added on or before this date.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub to: Option<NaiveDate>,
+    /// The page of results to show, numbered from 0.
+    #[serde(default)]
+    pub page: usize,
+}
+
+impl SearchQuery {
+    /// Returns the search to make, or `None` if no words were given to search for.
+    pub fn search(&self) -> Option<QuoteSearch> {
+        let text = self.q.trim();
+        if text.is_empty() {
+            None
+        } else {
+            Some(QuoteSearch {
+                text: text.to_owned(),
+                context_id: self.context_id,
+                quotee_id: self.quotee_id,
+                from: self.from,
+                to: self.to,
+            })
+        }
+    }
+
+    /// Returns the URL-encoded query parameters of the search, other than the page.
+    fn params_without_page(&self) -> Result<String, InternalError> {
+        let mut params = vec![format!("q={}", urlencode(&self.q)?)];
+        if let Some(context_id) = self.context_id {
+            params.push(format!("context_id={}", context_id));
+        }
+        if let Some(quotee_id) = self.quotee_id {
+            params.push(format!("quotee_id={}", quotee_id));
+        }
+        if let Some(from) = self.from {
+            params.push(format!("from={}", from));
+        }
+        if let Some(to) = self.to {
+            params.push(format!("to={}", to));
+        }
+        Ok(params.join("&"))
+    }
+}
+
+/// Deserialises a value which may be missing or blank, as HTML forms send for fields which are
+/// left empty.
+fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    match Option::<String>::deserialize(deserializer)?
+        .as_deref()
+        .map(str::trim)
+    {
+        None | Some("") => Ok(None),
+        Some(value) => value.parse().map(Some).map_err(de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Uri;
+
+    #[test]
+    fn blank_filters_are_ignored() {
+        let uri: Uri = "/search?q=fridge&context_id=&quotee_id=&from=&to="
+            .parse()
+            .unwrap();
+        let Query(query) = Query::<SearchQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(
+            query,
+            SearchQuery {
+                q: "fridge".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn blank_text_is_not_searched() {
+        let query = SearchQuery {
+            q: "  ".to_string(),
+            context_id: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(query.search(), None);
+    }
+
+    #[test]
+    fn params_keep_filters() {
+        let query = SearchQuery {
+            q: "the fridge".to_string(),
+            quotee_id: Some(3),
+            from: NaiveDate::from_ymd_opt(2026, 1, 2),
+            page: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            query.params_without_page().ok().as_deref(),
+            Some("q=the%20fridge&quotee_id=3&from=2026-01-02")
+        );
+    }
+}
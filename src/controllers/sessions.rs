@@ -2,7 +2,7 @@ use crate::{
     config::Config,
     errors::InternalError,
     model::User,
-    session::{Session, SessionClaims},
+    session::{Session, SessionClaims, SignupClaims},
 };
 use askama::{filters::urlencode, Template};
 use axum::{
@@ -15,9 +15,15 @@ use jsonwebtoken::{encode, EncodingKey, Header};
 use jsonwebtoken_google::Parser;
 use serde::Deserialize;
 use sqlx::{Pool, Postgres};
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use tower_cookies::{Cookie, Cookies};
 
+/// How long someone who has logged in with Google has to fill in the account creation form.
+const SIGNUP_DURATION: Duration = Duration::from_secs(60 * 60);
+
 pub async fn new(
     Extension(config): Extension<Arc<Config>>,
     session: Session,
@@ -42,7 +48,7 @@ pub async fn new(
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct RedirectQuery {
-    redirect: Option<String>,
+    pub redirect: Option<String>,
 }
 
 #[derive(Template)]
@@ -60,6 +66,47 @@ pub async fn google_auth(
     cookies: Cookies,
     Form(request): Form<GoogleAuthRequest>,
 ) -> Result<Response, InternalError> {
+    let google_claims = verify_google_auth(&config, &cookies, &request).await?;
+
+    // User has successfully authenticated with Google, see if they exist in our database.
+    if let Some(user) = User::fetch_by_email(&pool, &google_claims.email).await? {
+        log_in(&config, &cookies, user.id)?;
+        cookies.add(Cookie::new("notice", "Logged in successfully."));
+
+        Ok(Redirect::to(&redirect_path(query.redirect.as_deref())?).into_response())
+    } else {
+        // Remember who they are while they fill in the account creation form.
+        let key = EncodingKey::from_secret(config.secret.as_bytes());
+        let header = Header::default();
+        let now = SystemTime::now();
+        let expiry = now + SIGNUP_DURATION;
+        let claims = SignupClaims::new(google_claims.email, google_claims.name, now, expiry)?;
+        let token = encode(&header, &claims, &key)?;
+
+        // TODO: Set Secure, once we enforce https.
+        cookies.add(
+            Cookie::build(("signup", token))
+                .http_only(true)
+                .max_age(SIGNUP_DURATION.try_into()?)
+                .build(),
+        );
+
+        let new_user_url = if let Some(redirect) = query.redirect {
+            format!("/users/new?redirect={}", urlencode(redirect)?)
+        } else {
+            "/users/new".to_string()
+        };
+        Ok(Redirect::to(&new_user_url).into_response())
+    }
+}
+
+/// Checks the credential which Google posted after the user logged in with it, returning their
+/// details if it is valid and their email address has been verified.
+pub async fn verify_google_auth(
+    config: &Config,
+    cookies: &Cookies,
+    request: &GoogleAuthRequest,
+) -> Result<TokenClaims, InternalError> {
     if request.g_csrf_token
         != cookies
             .get("g_csrf_token")
@@ -77,36 +124,35 @@ pub async fn google_auth(
     if !google_claims.email_verified {
         return Err(InternalError::Internal(eyre!("Email not verified")));
     }
+    Ok(google_claims)
+}
 
-    // User has successfully authenticated with Google, see if they exist in our database.
-    if let Some(user) = User::fetch_by_email(&pool, &google_claims.email).await? {
-        // Issue a JWT for the user.
-        let key = EncodingKey::from_secret(config.secret.as_bytes());
-        let header = Header::default();
-        let now = SystemTime::now();
-        let expiry = now + config.session_duration;
-        let claims = SessionClaims::new(user.id, now, expiry)?;
-        let token = encode(&header, &claims, &key)?;
+/// Issues a session token for the given user, so that they are logged in.
+pub fn log_in(config: &Config, cookies: &Cookies, user_id: i32) -> Result<(), InternalError> {
+    let key = EncodingKey::from_secret(config.secret.as_bytes());
+    let header = Header::default();
+    let now = SystemTime::now();
+    let expiry = now + config.session_duration;
+    let claims = SessionClaims::new(user_id, now, expiry)?;
+    let token = encode(&header, &claims, &key)?;
 
-        // TODO: Set Secure, once we enforce https.
-        cookies.add(
-            Cookie::build(("session", token))
-                .http_only(true)
-                .max_age(config.session_duration.try_into()?)
-                .build(),
-        );
-
-        cookies.add(Cookie::new("notice", "Logged in successfully."));
+    // TODO: Set Secure, once we enforce https.
+    cookies.add(
+        Cookie::build(("session", token))
+            .http_only(true)
+            .max_age(config.session_duration.try_into()?)
+            .build(),
+    );
+    Ok(())
+}
 
-        let redirect: Uri = query.redirect.as_deref().unwrap_or("/").parse()?;
-        if redirect.host().is_some() || redirect.scheme().is_some() {
-            return Err(InternalError::Internal(eyre!("Invalid redirect path")));
-        }
-        Ok(Redirect::to(&redirect.to_string()).into_response())
-    } else {
-        // TODO: Redirect to the account creation form.
-        Ok(format!("No such user: {:?}", google_claims).into_response())
+/// Checks that the given redirect path is local to this site, defaulting to the home page.
+pub fn redirect_path(redirect: Option<&str>) -> Result<String, InternalError> {
+    let redirect: Uri = redirect.unwrap_or("/").parse()?;
+    if redirect.host().is_some() || redirect.scheme().is_some() {
+        return Err(InternalError::Internal(eyre!("Invalid redirect path")));
     }
+    Ok(redirect.to_string())
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -116,14 +162,13 @@ pub struct GoogleAuthRequest {
 }
 
 /// Claims from Google login.
+///
+/// The audience, issuer and expiry are checked by the `Parser`, so we don't need them here.
 #[derive(Debug, Deserialize)]
-struct TokenClaims {
+pub struct TokenClaims {
     pub email: String,
     pub email_verified: bool,
     pub name: String,
-    pub aud: String,
-    pub iss: String,
-    pub exp: u64,
 }
 
 pub async fn destroy(
@@ -134,9 +179,5 @@ pub async fn destroy(
 
     cookies.add(Cookie::new("notice", "You have been logged out."));
 
-    let redirect: Uri = query.redirect.as_deref().unwrap_or("/").parse()?;
-    if redirect.host().is_some() || redirect.scheme().is_some() {
-        return Err(InternalError::Internal(eyre!("Invalid redirect path")));
-    }
-    Ok(Redirect::to(&redirect.to_string()))
+    Ok(Redirect::to(&redirect_path(query.redirect.as_deref())?))
 }
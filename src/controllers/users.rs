@@ -1,24 +1,32 @@
+use super::sessions::{self, GoogleAuthRequest, RedirectQuery};
 use crate::{
-    atom::{comments::comments_to_atom, quotes::quotes_to_atom},
     config::Config,
     errors::InternalError,
+    feed::{comments::comments_to_feed, quotes::quotes_to_feed, FeedFormat, FeedPage},
     filters,
-    model::{CommentWithQuote, CommentWithQuotee, Context, QuoteWithUsers, User},
-    pagination::{PageOrGap, PaginationState, QueryPage},
-    responses::Atom,
-    session::Session,
+    model::{
+        violates_unique_index, ApiToken, ClaimRequest, CommentWithQuote, CommentWithQuotee,
+        Context, Notification, QuoteWithUsers, User,
+    },
+    pagination::{
+        ListPagination, PageOrGap, PaginationState, QueryFeedPage, QueryListPage, QueryPage,
+        LIST_PAGE_SIZE,
+    },
+    responses::{CacheableHtml, Validated},
+    session::{Session, SignupClaims},
 };
-use askama::Template;
+use askama::{filters::urlencode, Template};
 use axum::{
-    extract::{Extension, Path, Query},
-    response::Html,
+    extract::{Extension, Form, Path, Query},
+    http::HeaderMap,
+    response::{Html, IntoResponse, Redirect, Response},
 };
-use paginate::Pages;
+use serde::Deserialize;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
+use tower_cookies::{Cookie, Cookies};
 
 const QUOTES_PER_PAGE: usize = 10;
-const PAGINATION_WINDOW: usize = 2;
 
 pub async fn index(
     Extension(pool): Extension<Pool<Postgres>>,
@@ -43,14 +51,32 @@ pub async fn show(
     Query(query): Query<QueryPage>,
 ) -> Result<Html<String>, InternalError> {
     let user = User::fetch_one(&pool, user_id).await?;
-    let comments = CommentWithQuote::fetch_5_for_user(&pool, user_id).await?;
+    let comments =
+        CommentWithQuote::fetch_5_for_user(&pool, user_id, session.current_user_id()).await?;
 
-    let quote_count = QuoteWithUsers::count_for_quotee(&pool, user_id).await?;
-    let pages = Pages::new(quote_count, QUOTES_PER_PAGE);
-    let current_page = pages.with_offset(query.page);
-    let quotes =
-        QuoteWithUsers::fetch_page_for_quotee(&pool, user_id, &pages, &current_page).await?;
-    let contexts = Context::fetch_for_user(&pool, user_id).await?;
+    let quote_count =
+        QuoteWithUsers::count_for_quotee(&pool, user_id, session.current_user_id()).await?;
+    let pagination = PaginationState::new(quote_count, QUOTES_PER_PAGE, query.page);
+    let quotes = QuoteWithUsers::fetch_page_for_quotee(
+        &pool,
+        user_id,
+        &pagination.pages,
+        &pagination.current_page,
+        session.current_user_id(),
+    )
+    .await?;
+    let contexts = Context::fetch_for_user(&pool, user_id, session.current_user_id()).await?;
+    let can_vouch = match &session.current_user {
+        Some(current_user) if user.is_placeholder() => {
+            User::can_vouch_for(&pool, current_user.id, user_id).await?
+        }
+        _ => false,
+    };
+    let claim_requests = if can_vouch {
+        ClaimRequest::fetch_all_for_user(&pool, user_id).await?
+    } else {
+        vec![]
+    };
 
     let template = ShowTemplate {
         session,
@@ -58,11 +84,9 @@ pub async fn show(
         quotes,
         comments,
         contexts,
-        pagination: PaginationState {
-            pages,
-            current_page,
-            window_size: PAGINATION_WINDOW,
-        },
+        can_vouch,
+        claim_requests,
+        pagination,
     };
     Ok(Html(template.render()?))
 }
@@ -75,6 +99,10 @@ struct ShowTemplate {
     quotes: Vec<QuoteWithUsers>,
     comments: Vec<CommentWithQuote>,
     contexts: Vec<Context>,
+    /// Whether the current user may give an email address for this placeholder user.
+    can_vouch: bool,
+    /// Requests from people to become this placeholder user, if the current user may confirm them.
+    claim_requests: Vec<ClaimRequest>,
     pagination: PaginationState,
 }
 
@@ -82,16 +110,41 @@ pub async fn quotes(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Path(user_id): Path<i32>,
-) -> Result<Html<String>, InternalError> {
+    Query(query): Query<QueryListPage>,
+) -> Result<CacheableHtml, InternalError> {
     let user = User::fetch_one(&pool, user_id).await?;
-    let quotes = QuoteWithUsers::fetch_all_for_quotee(&pool, user_id).await?;
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after_for_quotee(
+            &pool,
+            user_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count =
+            QuoteWithUsers::count_for_quotee(&pool, user_id, session.current_user_id()).await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page_for_quotee(
+            &pool,
+            user_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
 
     let template = QuotesTemplate {
         session,
         user,
         quotes,
+        pagination,
     };
-    Ok(Html(template.render()?))
+    Ok(CacheableHtml(template.render()?))
 }
 
 #[derive(Template)]
@@ -100,35 +153,91 @@ struct QuotesTemplate {
     session: Session,
     user: User,
     quotes: Vec<QuoteWithUsers>,
+    pagination: ListPagination,
 }
 
-pub async fn quotes_atom(
+pub async fn quotes_feed<F: FeedFormat>(
     Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
     Path(user_id): Path<i32>,
-) -> Result<Atom, InternalError> {
+    Query(query): Query<QueryFeedPage>,
+    headers: HeaderMap,
+) -> Result<Validated<F>, InternalError> {
     let user = User::fetch_one(&pool, user_id).await?;
-    let quotes = QuoteWithUsers::fetch_all_for_quotee(&pool, user_id).await?;
+    let total = QuoteWithUsers::count_for_quotee(&pool, user_id, session.current_user_id()).await?;
+    let mut page = FeedPage::new(&query, total, config.feed_entries)?;
+    let quotes = match page.archive_entries(total, config.feed_entries) {
+        Some((pages, archive)) => {
+            QuoteWithUsers::fetch_page_for_quotee(
+                &pool,
+                user_id,
+                &pages,
+                &archive,
+                session.current_user_id(),
+            )
+            .await?
+        }
+        None => {
+            let quotes = QuoteWithUsers::fetch_after_for_quotee(
+                &pool,
+                user_id,
+                query.after.as_ref(),
+                config.feed_entries + 1,
+                session.current_user_id(),
+            )
+            .await?;
+            let (quotes, pagination) = ListPagination::after_cursor(quotes, config.feed_entries);
+            page.next = pagination.next;
+            quotes
+        }
+    };
     let title = format!("theQuotebook: Quotes by {}", user.fullname);
     let path = format!("/users/{}/quotes", user_id);
 
-    Ok(Atom(quotes_to_atom(quotes, title, &path, &config)?))
+    quotes_to_feed(quotes, title, &path, &page, &config, &headers)
 }
 
 pub async fn relevant_quotes(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Path(user_id): Path<i32>,
-) -> Result<Html<String>, InternalError> {
+    Query(query): Query<QueryListPage>,
+) -> Result<CacheableHtml, InternalError> {
     let user = User::fetch_one(&pool, user_id).await?;
-    let quotes = QuoteWithUsers::fetch_all_for_user_contexts(&pool, user_id).await?;
+    let (quotes, pagination) = if let Some(after) = &query.after {
+        let quotes = QuoteWithUsers::fetch_after_for_user_contexts(
+            &pool,
+            user_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(quotes, LIST_PAGE_SIZE)
+    } else {
+        let quote_count =
+            QuoteWithUsers::count_for_user_contexts(&pool, user_id, session.current_user_id())
+                .await?;
+        let numbered = PaginationState::new(quote_count, LIST_PAGE_SIZE, query.page);
+        let quotes = QuoteWithUsers::fetch_page_for_user_contexts(
+            &pool,
+            user_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(quotes, numbered)
+    };
 
     let template = RelevantQuotesTemplate {
         session,
         user,
         quotes,
+        pagination,
     };
-    Ok(Html(template.render()?))
+    Ok(CacheableHtml(template.render()?))
 }
 
 #[derive(Template)]
@@ -137,35 +246,92 @@ struct RelevantQuotesTemplate {
     session: Session,
     user: User,
     quotes: Vec<QuoteWithUsers>,
+    pagination: ListPagination,
 }
 
-pub async fn relevant_quotes_atom(
+pub async fn relevant_quotes_feed<F: FeedFormat>(
     Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
     Path(user_id): Path<i32>,
-) -> Result<Atom, InternalError> {
+    Query(query): Query<QueryFeedPage>,
+    headers: HeaderMap,
+) -> Result<Validated<F>, InternalError> {
     let user = User::fetch_one(&pool, user_id).await?;
-    let quotes = QuoteWithUsers::fetch_all_for_user_contexts(&pool, user_id).await?;
+    let total =
+        QuoteWithUsers::count_for_user_contexts(&pool, user_id, session.current_user_id()).await?;
+    let mut page = FeedPage::new(&query, total, config.feed_entries)?;
+    let quotes = match page.archive_entries(total, config.feed_entries) {
+        Some((pages, archive)) => {
+            QuoteWithUsers::fetch_page_for_user_contexts(
+                &pool,
+                user_id,
+                &pages,
+                &archive,
+                session.current_user_id(),
+            )
+            .await?
+        }
+        None => {
+            let quotes = QuoteWithUsers::fetch_after_for_user_contexts(
+                &pool,
+                user_id,
+                query.after.as_ref(),
+                config.feed_entries + 1,
+                session.current_user_id(),
+            )
+            .await?;
+            let (quotes, pagination) = ListPagination::after_cursor(quotes, config.feed_entries);
+            page.next = pagination.next;
+            quotes
+        }
+    };
     let title = format!("theQuotebook: Quotes of interest to {}", user.fullname);
     let path = format!("/users/{}/relevant_quotes", user_id);
 
-    Ok(Atom(quotes_to_atom(quotes, title, &path, &config)?))
+    quotes_to_feed(quotes, title, &path, &page, &config, &headers)
 }
 
 pub async fn relevant_comments(
     Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Path(user_id): Path<i32>,
-) -> Result<Html<String>, InternalError> {
+    Query(query): Query<QueryListPage>,
+) -> Result<CacheableHtml, InternalError> {
     let user = User::fetch_one(&pool, user_id).await?;
-    let comments = CommentWithQuotee::fetch_all_for_user_contexts(&pool, user_id).await?;
+    let (comments, pagination) = if let Some(after) = &query.after {
+        let comments = CommentWithQuotee::fetch_after_for_user_contexts(
+            &pool,
+            user_id,
+            Some(after),
+            LIST_PAGE_SIZE + 1,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::after_cursor(comments, LIST_PAGE_SIZE)
+    } else {
+        let comment_count =
+            CommentWithQuotee::count_for_user_contexts(&pool, user_id, session.current_user_id())
+                .await?;
+        let numbered = PaginationState::new(comment_count, LIST_PAGE_SIZE, query.page);
+        let comments = CommentWithQuotee::fetch_page_for_user_contexts(
+            &pool,
+            user_id,
+            &numbered.pages,
+            &numbered.current_page,
+            session.current_user_id(),
+        )
+        .await?;
+        ListPagination::numbered(comments, numbered)
+    };
 
     let template = RelevantCommentsTemplate {
         session,
         user,
         comments,
+        pagination,
     };
-    Ok(Html(template.render()?))
+    Ok(CacheableHtml(template.render()?))
 }
 
 #[derive(Template)]
@@ -174,22 +340,277 @@ struct RelevantCommentsTemplate {
     session: Session,
     user: User,
     comments: Vec<CommentWithQuotee>,
+    pagination: ListPagination,
 }
 
-pub async fn relevant_comments_atom(
+pub async fn relevant_comments_feed<F: FeedFormat>(
     Extension(config): Extension<Arc<Config>>,
     Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
     Path(user_id): Path<i32>,
-) -> Result<Atom, InternalError> {
+    Query(query): Query<QueryFeedPage>,
+    headers: HeaderMap,
+) -> Result<Validated<F>, InternalError> {
     let user = User::fetch_one(&pool, user_id).await?;
-    let comments = CommentWithQuotee::fetch_all_for_user_contexts(&pool, user_id).await?;
+    let total =
+        CommentWithQuotee::count_for_user_contexts(&pool, user_id, session.current_user_id())
+            .await?;
+    let mut page = FeedPage::new(&query, total, config.feed_entries)?;
+    let comments = match page.archive_entries(total, config.feed_entries) {
+        Some((pages, archive)) => {
+            CommentWithQuotee::fetch_page_for_user_contexts(
+                &pool,
+                user_id,
+                &pages,
+                &archive,
+                session.current_user_id(),
+            )
+            .await?
+        }
+        None => {
+            let comments = CommentWithQuotee::fetch_after_for_user_contexts(
+                &pool,
+                user_id,
+                query.after.as_ref(),
+                config.feed_entries + 1,
+                session.current_user_id(),
+            )
+            .await?;
+            let (comments, pagination) =
+                ListPagination::after_cursor(comments, config.feed_entries);
+            page.next = pagination.next;
+            comments
+        }
+    };
     let title = format!("theQuotebook: Comments of interest to {}", user.fullname);
     let path = format!("/users/{}/relevant_comments", user_id);
 
-    Ok(Atom(comments_to_atom(comments, title, &path, &config)?))
+    comments_to_feed(comments, title, &path, &page, &config, &headers)
+}
+
+pub async fn new(
+    Extension(config): Extension<Arc<Config>>,
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Query(query): Query<RedirectQuery>,
+    cookies: Cookies,
+) -> Result<Html<String>, InternalError> {
+    // The user must have just logged in with Google, with an email address we don't know.
+    let claims =
+        SignupClaims::from_cookies(&config, &cookies).ok_or(InternalError::Unauthorised)?;
+
+    let placeholders = User::fetch_placeholder_matches(&pool, &claims.name).await?;
+
+    let template = NewTemplate {
+        session,
+        form: UserForm {
+            error_messages: String::default(),
+            fullname: claims.name,
+            username: String::default(),
+            email_address: claims.email,
+            quotes_need_approval: false,
+        },
+        form_action: new_user_action(&query)?,
+        placeholders,
+        redirect_query: redirect_query(&query)?,
+    };
+    Ok(Html(template.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "users/new.html")]
+struct NewTemplate {
+    session: Session,
+    form: UserForm,
+    form_action: String,
+    /// Placeholder users who the new user might be, and so could claim instead.
+    placeholders: Vec<User>,
+    redirect_query: String,
+}
+
+/// Returns the query string to preserve the given redirect across form submissions.
+fn redirect_query(query: &RedirectQuery) -> Result<String, InternalError> {
+    Ok(if let Some(redirect) = &query.redirect {
+        format!("?redirect={}", urlencode(redirect)?)
+    } else {
+        String::default()
+    })
+}
+
+/// Returns the URL to which the new user form should be submitted, to preserve the given redirect.
+fn new_user_action(query: &RedirectQuery) -> Result<String, InternalError> {
+    Ok(format!("/users{}", redirect_query(query)?))
+}
+
+pub async fn create(
+    Extension(config): Extension<Arc<Config>>,
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Query(query): Query<RedirectQuery>,
+    cookies: Cookies,
+    Form(request): Form<UserRequest>,
+) -> Result<Response, InternalError> {
+    // The user must have just logged in with Google, with an email address we don't know.
+    let claims =
+        SignupClaims::from_cookies(&config, &cookies).ok_or(InternalError::Unauthorised)?;
+    let request = UserRequest {
+        email_address: claims.email,
+        ..request
+    };
+
+    if let Err(form) = request.validate(&pool, None).await? {
+        let placeholders = User::fetch_placeholder_matches(&pool, &request.fullname).await?;
+        let template = NewTemplate {
+            session,
+            form,
+            form_action: new_user_action(&query)?,
+            placeholders,
+            redirect_query: redirect_query(&query)?,
+        };
+        return Ok(Html(template.render()?).into_response());
+    }
+
+    let user = match User::create(
+        &pool,
+        request.fullname.trim(),
+        request.username(),
+        &request.email_address,
+    )
+    .await
+    {
+        Ok(user) => user,
+        Err(e) => {
+            // Another user may have taken the username or email address since they were
+            // validated.
+            let form = request.form_for_error(e)?;
+            let placeholders = User::fetch_placeholder_matches(&pool, &request.fullname).await?;
+            let template = NewTemplate {
+                session,
+                form,
+                form_action: new_user_action(&query)?,
+                placeholders,
+                redirect_query: redirect_query(&query)?,
+            };
+            return Ok(Html(template.render()?).into_response());
+        }
+    };
+    cookies.remove(Cookie::new("signup", ""));
+    sessions::log_in(&config, &cookies, user.id)?;
+    cookies.add(Cookie::new(
+        "notice",
+        "Welcome to theQuotebook! Your account has been created.",
+    ));
+
+    Ok(Redirect::to(&sessions::redirect_path(query.redirect.as_deref())?).into_response())
+}
+
+pub async fn claim(
+    Extension(config): Extension<Arc<Config>>,
+    Extension(pool): Extension<Pool<Postgres>>,
+    Path(user_id): Path<i32>,
+    Query(query): Query<RedirectQuery>,
+    cookies: Cookies,
+) -> Result<Redirect, InternalError> {
+    // The user must have just logged in with Google, with an email address we don't know.
+    let claims =
+        SignupClaims::from_cookies(&config, &cookies).ok_or(InternalError::Unauthorised)?;
+
+    // Only the placeholder users which were offered to them can be claimed.
+    let user = User::fetch_placeholder_matches(&pool, &claims.name)
+        .await?
+        .into_iter()
+        .find(|placeholder| placeholder.id == user_id)
+        .ok_or(InternalError::Unauthorised)?;
+
+    // Anyone could say that they are the placeholder user, so someone who knows them must confirm
+    // it before they get its quotes.
+    if ClaimRequest::create(&pool, user_id, &claims.email, &claims.name).await? {
+        for voucher_id in User::fetch_voucher_ids(&pool, user_id).await? {
+            Notification::create(
+                &pool,
+                voucher_id,
+                &format!(
+                    "{} ({}) says they are {}. Please confirm it if so.",
+                    claims.name, claims.email, user.fullname
+                ),
+                Some(&format!("/users/{}", user_id)),
+            )
+            .await?;
+        }
+    }
+    cookies.add(Cookie::new(
+        "notice",
+        format!(
+            "We've asked people who know {} to confirm that it's you. Once they have, log in again \
+             to get your quotes.",
+            user.fullname
+        ),
+    ));
+
+    Ok(Redirect::to(&sessions::redirect_path(
+        query.redirect.as_deref(),
+    )?))
+}
+
+/// The fields submitted by the vouch form in `users/show.html`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct VouchRequest {
+    email_address: String,
+}
+
+pub async fn vouch(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<VouchRequest>,
+) -> Result<Redirect, InternalError> {
+    let user = User::fetch_one(&pool, user_id).await?;
+
+    // There must be a user logged in, who is a member of a context in which the placeholder user
+    // has been quoted.
+    let current_user = session.current_user.ok_or(InternalError::Unauthorised)?;
+    if !user.is_placeholder() || !User::can_vouch_for(&pool, current_user.id, user_id).await? {
+        return Err(InternalError::Unauthorised);
+    }
+
+    let email_address = request.email_address.trim();
+    if !User::is_valid_email_address(email_address) {
+        cookies.add(Cookie::new(
+            "error",
+            format!("'{}' is not a valid email address.", email_address),
+        ));
+    } else if User::fetch_by_email(&pool, email_address).await?.is_some() {
+        cookies.add(Cookie::new("error", email_address_taken(email_address)));
+    } else {
+        match User::claim(&pool, user_id, email_address).await {
+            Ok(true) => {
+                ClaimRequest::delete_all_for_user(&pool, user_id).await?;
+                cookies.add(Cookie::new(
+                    "notice",
+                    format!(
+                        "{} can now log in with {} to claim their quotes.",
+                        user.fullname, email_address
+                    ),
+                ));
+            }
+            Ok(false) => cookies.add(Cookie::new(
+                "error",
+                format!("{} has already been claimed by someone.", user.fullname),
+            )),
+            // Another user may have taken the email address since it was checked.
+            Err(e) if violates_unique_index(&e, User::EMAIL_ADDRESS_INDEX) => {
+                cookies.add(Cookie::new("error", email_address_taken(email_address)))
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(Redirect::to(&format!("/users/{}", user_id)))
 }
 
 pub async fn edit(
+    Extension(pool): Extension<Pool<Postgres>>,
     session: Session,
     Path(user_id): Path<i32>,
 ) -> Result<Html<String>, InternalError> {
@@ -202,24 +623,308 @@ pub async fn edit(
         return Err(InternalError::Unauthorised);
     }
 
-    let template = EditTemplate {
-        session,
-        user,
-        form: UserForm {
-            error_messages: "".to_string(),
-        },
+    let quotes_need_approval = User::quotes_need_approval(&pool, user_id).await?;
+    let form = UserForm {
+        quotes_need_approval,
+        ..user.clone().into()
     };
+
+    let template = EditTemplate::new(&pool, session, user, form, None).await?;
     Ok(Html(template.render()?))
 }
 
 #[derive(Template)]
 #[template(path = "users/edit.html")]
-struct EditTemplate {
+pub struct EditTemplate {
     session: Session,
     user: User,
     form: UserForm,
+    api_tokens: Vec<ApiToken>,
+    /// An API token which was just created, to show to the user this once.
+    new_api_token: Option<String>,
 }
 
+impl EditTemplate {
+    async fn new(
+        pool: &Pool<Postgres>,
+        session: Session,
+        user: User,
+        form: UserForm,
+        new_api_token: Option<String>,
+    ) -> Result<Self, InternalError> {
+        let api_tokens = ApiToken::fetch_all_for_user(pool, user.id).await?;
+        Ok(Self {
+            session,
+            user,
+            form,
+            api_tokens,
+            new_api_token,
+        })
+    }
+
+    /// Returns the edit page for the given user's own profile, showing the API token which they
+    /// just created.
+    pub async fn with_new_api_token(
+        pool: &Pool<Postgres>,
+        session: Session,
+        user: User,
+        new_api_token: String,
+    ) -> Result<Self, InternalError> {
+        let quotes_need_approval = User::quotes_need_approval(pool, user.id).await?;
+        let form = UserForm {
+            quotes_need_approval,
+            ..user.clone().into()
+        };
+        Self::new(pool, session, user, form, Some(new_api_token)).await
+    }
+}
+
+#[derive(Clone, Debug, Default)]
 struct UserForm {
     error_messages: String,
+    fullname: String,
+    username: String,
+    email_address: String,
+    quotes_need_approval: bool,
+}
+
+impl From<User> for UserForm {
+    fn from(user: User) -> Self {
+        Self {
+            error_messages: String::default(),
+            fullname: user.fullname,
+            username: user.username.unwrap_or_default(),
+            email_address: user.email_address.unwrap_or_default(),
+            quotes_need_approval: false,
+        }
+    }
+}
+
+/// The fields submitted by `users/form.html` or `users/new.html`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct UserRequest {
+    fullname: String,
+    username: String,
+    /// This is never taken from the form: a new account uses the verified email address from
+    /// Google, and changing it afterwards needs the user to log in with Google again, as it is how
+    /// they log in.
+    #[serde(skip)]
+    email_address: String,
+    /// Whether quotes about the user must be approved by them before they are shown. This isn't
+    /// included when creating a new account.
+    #[serde(default)]
+    quotes_need_approval: bool,
+}
+
+impl UserRequest {
+    fn username(&self) -> Option<&str> {
+        Some(self.username.trim()).filter(|username| !username.is_empty())
+    }
+
+    /// Checks that the request is valid, and in particular that the username isn't already used
+    /// by another user than the one being edited, if any. The email address has already been
+    /// verified by Google.
+    ///
+    /// If there is a problem with the request, returns a form to show the user again with
+    /// appropriate error messages.
+    async fn validate(
+        &self,
+        pool: &Pool<Postgres>,
+        user_id: Option<i32>,
+    ) -> Result<Result<(), UserForm>, InternalError> {
+        let mut errors = vec![];
+
+        if self.fullname.trim().is_empty() {
+            errors.push("Full name can't be blank.".to_string());
+        }
+
+        if let Some(username) = self.username() {
+            if !User::is_valid_username(username) {
+                errors.push(
+                    "Username must be 3 to 40 letters, numbers, dots, dashes or underscores."
+                        .to_string(),
+                );
+            } else if let Some(existing) = User::fetch_by_username(pool, username).await? {
+                if Some(existing.id) != user_id {
+                    errors.push(username_taken(username));
+                }
+            }
+        }
+
+        Ok(if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.form_with_errors(&errors))
+        })
+    }
+
+    /// Returns a form to show the user again with the given error messages.
+    fn form_with_errors(&self, errors: &[String]) -> UserForm {
+        UserForm {
+            error_messages: errors.join(" "),
+            fullname: self.fullname.clone(),
+            username: self.username.clone(),
+            email_address: self.email_address.clone(),
+            quotes_need_approval: self.quotes_need_approval,
+        }
+    }
+
+    /// Returns a form to show the user again if the given error from saving the request is because
+    /// another user has since taken its username or email address, or otherwise the error itself.
+    fn form_for_error(&self, error: sqlx::Error) -> Result<UserForm, InternalError> {
+        let message = if violates_unique_index(&error, User::USERNAME_INDEX) {
+            username_taken(self.username.trim())
+        } else if violates_unique_index(&error, User::EMAIL_ADDRESS_INDEX) {
+            email_address_taken(&self.email_address)
+        } else {
+            return Err(error.into());
+        };
+        Ok(self.form_with_errors(&[message]))
+    }
+}
+
+/// Returns the error message for when another user already has the given username.
+fn username_taken(username: &str) -> String {
+    format!("The username '{}' is already taken.", username)
+}
+
+/// Returns the error message for when another user already has the given email address.
+fn email_address_taken(email_address: &str) -> String {
+    format!(
+        "The email address '{}' is already used by someone else.",
+        email_address
+    )
+}
+
+pub async fn update(
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<UserRequest>,
+) -> Result<Response, InternalError> {
+    // There must be a user logged in, and they can only edit their own profile.
+    let user = session
+        .current_user
+        .clone()
+        .ok_or(InternalError::Unauthorised)?;
+    if user.id != user_id {
+        return Err(InternalError::Unauthorised);
+    }
+
+    if let Err(form) = request.validate(&pool, Some(user_id)).await? {
+        let form = UserForm {
+            email_address: user.email_address.clone().unwrap_or_default(),
+            ..form
+        };
+        let template = EditTemplate::new(&pool, session, user, form, None).await?;
+        return Ok(Html(template.render()?).into_response());
+    }
+
+    if let Err(e) = User::update(
+        &pool,
+        user_id,
+        request.fullname.trim(),
+        request.username(),
+        request.quotes_need_approval,
+    )
+    .await
+    {
+        // Another user may have taken the username since it was validated.
+        let form = UserForm {
+            email_address: user.email_address.clone().unwrap_or_default(),
+            ..request.form_for_error(e)?
+        };
+        let template = EditTemplate::new(&pool, session, user, form, None).await?;
+        return Ok(Html(template.render()?).into_response());
+    }
+    cookies.add(Cookie::new(
+        "notice",
+        "Your profile was successfully updated.",
+    ));
+
+    Ok(Redirect::to(&format!("/users/{}", user_id)).into_response())
+}
+
+pub async fn edit_email(
+    Extension(config): Extension<Arc<Config>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+) -> Result<Html<String>, InternalError> {
+    // There must be a user logged in, and they can only change their own email address.
+    let user = session
+        .current_user
+        .clone()
+        .ok_or(InternalError::Unauthorised)?;
+    if user.id != user_id {
+        return Err(InternalError::Unauthorised);
+    }
+
+    let template = EditEmailTemplate {
+        session,
+        user,
+        google_client_id: config.google_client_id.to_owned(),
+        auth_url: format!("{}/users/{}/email", config.base_url, user_id),
+    };
+    Ok(Html(template.render()?))
+}
+
+#[derive(Template)]
+#[template(path = "users/email.html")]
+struct EditEmailTemplate {
+    session: Session,
+    user: User,
+    google_client_id: String,
+    auth_url: String,
+}
+
+/// Changes the user's email address to the one they have just logged in to Google with, so that it
+/// is verified.
+pub async fn update_email(
+    Extension(config): Extension<Arc<Config>>,
+    Extension(pool): Extension<Pool<Postgres>>,
+    session: Session,
+    Path(user_id): Path<i32>,
+    cookies: Cookies,
+    Form(request): Form<GoogleAuthRequest>,
+) -> Result<Redirect, InternalError> {
+    // There must be a user logged in, and they can only change their own email address.
+    let user = session
+        .current_user
+        .as_ref()
+        .ok_or(InternalError::Unauthorised)?;
+    if user.id != user_id {
+        return Err(InternalError::Unauthorised);
+    }
+
+    let google_claims = sessions::verify_google_auth(&config, &cookies, &request).await?;
+    let email_address = google_claims.email;
+    if user.email_address.as_deref() == Some(email_address.as_str()) {
+        cookies.add(Cookie::new(
+            "notice",
+            format!("Your email address is already {}.", email_address),
+        ));
+    } else if User::fetch_by_email(&pool, &email_address)
+        .await?
+        .is_some_and(|existing| existing.id != user_id)
+    {
+        cookies.add(Cookie::new("error", email_address_taken(&email_address)));
+    } else {
+        match User::set_email_address(&pool, user_id, &email_address).await {
+            Ok(()) => cookies.add(Cookie::new(
+                "notice",
+                format!(
+                    "Your email address is now {}. Log in with it from now on.",
+                    email_address
+                ),
+            )),
+            // Another user may have taken the email address since it was checked.
+            Err(e) if violates_unique_index(&e, User::EMAIL_ADDRESS_INDEX) => {
+                cookies.add(Cookie::new("error", email_address_taken(&email_address)))
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(Redirect::to(&format!("/users/{}/edit", user_id)))
 }
@@ -40,3 +40,13 @@ fn internal_error_response<E: Debug>(e: E) -> Response {
     )
         .into_response()
 }
+
+impl From<InternalError> for Report {
+    fn from(error: InternalError) -> Self {
+        match error {
+            InternalError::Internal(report) => report,
+            InternalError::NotFound => eyre::eyre!("Not found"),
+            InternalError::Unauthorised => eyre::eyre!("Unauthorised"),
+        }
+    }
+}
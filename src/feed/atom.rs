@@ -0,0 +1,232 @@
+use super::{Feed, FeedEntry, FeedFormat, FEED_HISTORY_NAMESPACE};
+use crate::{config::Config, responses::Atom};
+use atom_syndication::{
+    extension::ExtensionBuilder, ContentBuilder, Entry, EntryBuilder, FeedBuilder,
+    GeneratorBuilder, Link, LinkBuilder, PersonBuilder,
+};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+impl FeedFormat for Atom {
+    const EXTENSION: &'static str = "atom";
+    const MIME_TYPE: &'static str = "application/atom+xml";
+
+    fn render(feed: Feed, config: &Config) -> Self {
+        Atom(
+            feed_builder(&feed, config)
+                .updated(feed.updated.unwrap_or(DateTime::<Utc>::MIN_UTC))
+                .entries(feed.entries.into_iter().map(entry).collect::<Vec<_>>())
+                .build(),
+        )
+    }
+}
+
+/// Returns a builder for the given page of a feed, with links to the other pages of the feed.
+fn feed_builder(feed: &Feed, config: &Config) -> FeedBuilder {
+    let feed_url = feed.url::<Atom>(config);
+
+    let mut builder = FeedBuilder::default();
+    builder
+        .title(feed.title.clone())
+        .link(atom_link("self", feed.self_url::<Atom>(config)))
+        .link(
+            LinkBuilder::default()
+                .rel("alternate")
+                .mime_type("text/html".to_string())
+                .href(config.absolute_url(&feed.path))
+                .build(),
+        )
+        .id(&feed_url)
+        .generator(
+            GeneratorBuilder::default()
+                .value("theQuotebook")
+                .uri(config.absolute_url("/"))
+                .build(),
+        );
+    if let Some(next) = &feed.page.next {
+        builder.link(atom_link("next", feed.page_url::<Atom>(next, config)));
+    }
+    if let Some(prev_archive) = feed.prev_archive() {
+        builder.link(atom_link(
+            "prev-archive",
+            feed.archive_url::<Atom>(prev_archive, config),
+        ));
+    }
+    if let Some(next_archive) = feed.next_archive() {
+        builder.link(atom_link(
+            "next-archive",
+            feed.archive_url::<Atom>(next_archive, config),
+        ));
+    }
+    if feed.page.after.is_some() {
+        builder.link(atom_link("first", feed_url.clone()));
+    }
+    if feed.page.archive.is_some() {
+        builder
+            .link(atom_link("current", feed_url.clone()))
+            .namespace(("fh".to_string(), FEED_HISTORY_NAMESPACE.to_string()))
+            .extension((
+                "fh".to_string(),
+                BTreeMap::from([(
+                    "archive".to_string(),
+                    vec![ExtensionBuilder::default().name("fh:archive").build()],
+                )]),
+            ));
+    }
+    builder
+}
+
+/// Returns a link to another page of the same feed.
+fn atom_link(rel: &str, href: String) -> Link {
+    LinkBuilder::default()
+        .rel(rel)
+        .mime_type(Atom::MIME_TYPE.to_string())
+        .href(href)
+        .build()
+}
+
+fn entry(entry: FeedEntry) -> Entry {
+    EntryBuilder::default()
+        .title(entry.title)
+        .link(
+            LinkBuilder::default()
+                .rel("alternate")
+                .mime_type("text/html".to_string())
+                .href(&entry.url)
+                .build(),
+        )
+        .id(entry.url)
+        .updated(entry.updated)
+        .published(Some(entry.published.into()))
+        .author(
+            PersonBuilder::default()
+                .name(entry.author_name)
+                .uri(entry.author_url)
+                .build(),
+        )
+        .content(
+            ContentBuilder::default()
+                .content_type("html".to_string())
+                .value(entry.content)
+                .build(),
+        )
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::{
+        tests::{config, cursor, feed},
+        FeedPage,
+    };
+
+    fn link_hrefs(feed: &Feed) -> Vec<(String, String)> {
+        feed_builder(feed, &config())
+            .build()
+            .links
+            .into_iter()
+            .map(|link| (link.rel, link.href))
+            .collect()
+    }
+
+    #[test]
+    fn subscription_document_links_to_older_entries() {
+        let feed = feed(FeedPage {
+            next: Some(cursor(42)),
+            archive_count: 3,
+            ..FeedPage::default()
+        });
+        assert_eq!(
+            link_hrefs(&feed),
+            vec![
+                (
+                    "self".to_string(),
+                    "https://quotes.example.com/quotes.atom".to_string()
+                ),
+                (
+                    "alternate".to_string(),
+                    "https://quotes.example.com/quotes".to_string()
+                ),
+                (
+                    "next".to_string(),
+                    format!(
+                        "https://quotes.example.com/quotes.atom?after={}",
+                        cursor(42)
+                    )
+                ),
+                (
+                    "prev-archive".to_string(),
+                    "https://quotes.example.com/quotes.atom?archive=2".to_string()
+                ),
+            ]
+        );
+        let xml = Atom::render(feed, &config()).0.to_string();
+        assert!(!xml.contains("fh:archive"));
+        assert!(xml.contains("<updated>2025-10-20T00:00:00+00:00</updated>"));
+    }
+
+    #[test]
+    fn paged_view_is_not_archived() {
+        let feed = feed(FeedPage {
+            after: Some(cursor(41)),
+            next: Some(cursor(42)),
+            archive_count: 3,
+            ..FeedPage::default()
+        });
+        let links = link_hrefs(&feed);
+        assert!(links.contains(&(
+            "first".to_string(),
+            "https://quotes.example.com/quotes.atom".to_string()
+        )));
+        assert!(!links.iter().any(|(rel, _)| rel.ends_with("archive")));
+        assert!(!Atom::render(feed, &config())
+            .0
+            .to_string()
+            .contains("fh:archive"));
+    }
+
+    #[test]
+    fn archive_document_is_marked() {
+        let feed = feed(FeedPage {
+            archive: Some(1),
+            archive_count: 3,
+            ..FeedPage::default()
+        });
+        let links = link_hrefs(&feed);
+        for (rel, href) in [
+            ("self", "https://quotes.example.com/quotes.atom?archive=1"),
+            (
+                "prev-archive",
+                "https://quotes.example.com/quotes.atom?archive=0",
+            ),
+            (
+                "next-archive",
+                "https://quotes.example.com/quotes.atom?archive=2",
+            ),
+            ("current", "https://quotes.example.com/quotes.atom"),
+        ] {
+            assert!(links.contains(&(rel.to_string(), href.to_string())));
+        }
+        assert!(!links.iter().any(|(rel, _)| rel == "next"));
+        let xml = Atom::render(feed, &config()).0.to_string();
+        assert!(xml.contains(r#"xmlns:fh="http://purl.org/syndication/history/1.0""#));
+        assert!(xml.contains("<fh:archive>"));
+    }
+
+    #[test]
+    fn oldest_and_newest_archives() {
+        let oldest = link_hrefs(&feed(FeedPage {
+            archive: Some(0),
+            archive_count: 2,
+            ..FeedPage::default()
+        }));
+        assert!(!oldest.iter().any(|(rel, _)| rel == "prev-archive"));
+        let newest = link_hrefs(&feed(FeedPage {
+            archive: Some(1),
+            archive_count: 2,
+            ..FeedPage::default()
+        }));
+        assert!(!newest.iter().any(|(rel, _)| rel == "next-archive"));
+    }
+}
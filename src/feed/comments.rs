@@ -0,0 +1,71 @@
+use super::{Feed, FeedEntry, FeedFormat, FeedPage};
+use crate::{
+    config::Config,
+    errors::InternalError,
+    filters,
+    model::{Comment, CommentWithQuotee, User},
+    responses::Validated,
+};
+use askama::Template;
+use axum::http::HeaderMap;
+
+pub fn comments_to_feed<F: FeedFormat>(
+    comments: Vec<CommentWithQuotee>,
+    title: String,
+    path: &str,
+    page: &FeedPage,
+    config: &Config,
+    request_headers: &HeaderMap,
+) -> Result<Validated<F>, InternalError> {
+    // The feed also depends on its format and where it is, as well as on what is in it.
+    let data = (comments, title, path, page, F::EXTENSION, &config.base_url);
+    Validated::new(data, request_headers, |(comments, title, ..)| {
+        let entries = comments
+            .into_iter()
+            .map(|comment| comment_entry(&config.base_url, comment))
+            .collect::<Result<Vec<_>, InternalError>>()?;
+        Ok(F::render(Feed::new(title, path, page, entries), config))
+    })
+}
+
+fn comment_entry(base_url: &str, comment: CommentWithQuotee) -> Result<FeedEntry, InternalError> {
+    Ok(FeedEntry {
+        title: format!(
+            "{} on {} ({})",
+            comment.user.username_or_fullname(),
+            comment.quote_text,
+            comment.quotee.fullname,
+        ),
+        url: format!(
+            "{}/quotes/{}/comments/{}",
+            base_url, comment.comment.quote_id, comment.comment.id
+        ),
+        published: comment.comment.created_at,
+        updated: comment.comment.updated_at,
+        author_name: comment.user.username_or_fullname().to_owned(),
+        author_url: format!("{}/users/{}", base_url, comment.user.id),
+        content: chatty_comment(comment, base_url)?,
+    })
+}
+
+fn chatty_comment(comment: CommentWithQuotee, base_url: &str) -> askama::Result<String> {
+    let template = ChattyCommentTemplate {
+        comment: comment.comment,
+        quote_text: comment.quote_text,
+        user: comment.user,
+        quotee: comment.quotee,
+        base_url: base_url.to_owned(),
+    };
+
+    template.render()
+}
+
+#[derive(Template)]
+#[template(path = "shared/chatty_comment.html")]
+struct ChattyCommentTemplate {
+    comment: Comment,
+    quote_text: String,
+    user: User,
+    quotee: User,
+    base_url: String,
+}
@@ -0,0 +1,152 @@
+//! Feeds in the JSON Feed 1.1 format, from <https://www.jsonfeed.org/version/1.1/>.
+
+use super::{Feed, FeedEntry, FeedFormat};
+use crate::{config::Config, responses::JsonFeed};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+const VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JsonFeedDocument {
+    pub version: &'static str,
+    pub title: String,
+    pub home_page_url: String,
+    pub feed_url: String,
+    /// The URL of the next page of older items, if there are any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_url: Option<String>,
+    pub items: Vec<JsonFeedItem>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JsonFeedItem {
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub content_html: String,
+    pub date_published: DateTime<Utc>,
+    pub date_modified: DateTime<Utc>,
+    pub authors: Vec<JsonFeedAuthor>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JsonFeedAuthor {
+    pub name: String,
+    pub url: String,
+}
+
+impl FeedFormat for JsonFeed {
+    const EXTENSION: &'static str = "json";
+    const MIME_TYPE: &'static str = "application/feed+json";
+
+    fn render(feed: Feed, config: &Config) -> Self {
+        // JSON Feed has no equivalent of archive documents, so every page just links to the next
+        // page of older items: an archive document to the archive before it.
+        let next_url = match feed.page.archive {
+            Some(_) => feed
+                .prev_archive()
+                .map(|prev_archive| feed.archive_url::<JsonFeed>(prev_archive, config)),
+            None => feed
+                .page
+                .next
+                .as_ref()
+                .map(|next| feed.page_url::<JsonFeed>(next, config)),
+        };
+        JsonFeed(JsonFeedDocument {
+            version: VERSION,
+            home_page_url: config.absolute_url(&feed.path),
+            // This is the URL to subscribe to, so it's always the first page.
+            feed_url: feed.url::<JsonFeed>(config),
+            next_url,
+            title: feed.title,
+            items: feed.entries.into_iter().map(item).collect(),
+        })
+    }
+}
+
+fn item(entry: FeedEntry) -> JsonFeedItem {
+    JsonFeedItem {
+        id: entry.url.clone(),
+        url: entry.url,
+        title: entry.title,
+        content_html: entry.content,
+        date_published: entry.published,
+        date_modified: entry.updated,
+        authors: vec![JsonFeedAuthor {
+            name: entry.author_name,
+            url: entry.author_url,
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::{
+        tests::{config, cursor, feed},
+        FeedPage,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn links_to_next_page() {
+        let document = JsonFeed::render(
+            feed(FeedPage {
+                after: Some(cursor(41)),
+                next: Some(cursor(42)),
+                ..FeedPage::default()
+            }),
+            &config(),
+        )
+        .0;
+        let json = serde_json::to_value(&document).unwrap();
+        assert_eq!(json["version"], VERSION);
+        assert_eq!(json["feed_url"], "https://quotes.example.com/quotes.json");
+        assert_eq!(
+            json["next_url"],
+            format!(
+                "https://quotes.example.com/quotes.json?after={}",
+                cursor(42)
+            )
+        );
+        assert_eq!(
+            json["items"][0],
+            json!({
+                "id": "https://quotes.example.com/quotes/2",
+                "url": "https://quotes.example.com/quotes/2",
+                "title": "Someone: Quote 2",
+                "content_html": "<p>Quote</p>",
+                "date_published": "2025-10-18T00:00:00Z",
+                "date_modified": "2025-10-19T00:00:00Z",
+                "authors": [{"name": "someone", "url": "https://quotes.example.com/users/1"}],
+            })
+        );
+    }
+
+    #[test]
+    fn archive_links_to_older_archive() {
+        let document = JsonFeed::render(
+            feed(FeedPage {
+                archive: Some(1),
+                archive_count: 2,
+                ..FeedPage::default()
+            }),
+            &config(),
+        )
+        .0;
+        assert_eq!(document.feed_url, "https://quotes.example.com/quotes.json");
+        assert_eq!(
+            document.next_url.as_deref(),
+            Some("https://quotes.example.com/quotes.json?archive=0")
+        );
+    }
+
+    #[test]
+    fn last_page_has_no_next_url() {
+        let document = JsonFeed::render(feed(FeedPage::default()), &config()).0;
+        let json = serde_json::to_value(&document).unwrap();
+        assert_eq!(json["feed_url"], "https://quotes.example.com/quotes.json");
+        assert!(json.get("next_url").is_none());
+    }
+}
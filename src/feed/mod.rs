@@ -0,0 +1,287 @@
+//! Feeds of quotes and comments, in Atom, RSS 2.0 and JSON Feed formats.
+//!
+//! The entries of a feed are built from the model the same way for every format, and then each
+//! format renders them with its own links to the other pages of the feed.
+
+pub mod atom;
+pub mod comments;
+pub mod json;
+pub mod quotes;
+pub mod rss;
+
+use crate::{
+    config::Config,
+    errors::InternalError,
+    pagination::{Cursor, QueryFeedPage},
+};
+use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
+use paginate::{Page, Pages};
+
+/// The namespace of the feed history elements from RFC 5005.
+const FEED_HISTORY_NAMESPACE: &str = "http://purl.org/syndication/history/1.0";
+
+/// A format which feeds can be rendered in, as a response.
+pub trait FeedFormat: IntoResponse + Sized {
+    /// The extension added to the path of the HTML page for the URL of the feed.
+    const EXTENSION: &'static str;
+    /// The MIME type of the feed.
+    const MIME_TYPE: &'static str;
+
+    /// Renders the given feed in this format.
+    fn render(feed: Feed, config: &Config) -> Self;
+}
+
+/// A page of a feed, before it is rendered in any particular format.
+#[derive(Clone, Debug)]
+pub struct Feed {
+    pub title: String,
+    /// The path of the HTML page with the same list as the feed.
+    pub path: String,
+    pub page: FeedPage,
+    /// When the most recently updated entry was last updated, or `None` if there are no entries.
+    pub updated: Option<DateTime<Utc>>,
+    pub entries: Vec<FeedEntry>,
+}
+
+impl Feed {
+    /// Returns a new page of a feed with the given entries.
+    fn new(title: String, path: &str, page: &FeedPage, entries: Vec<FeedEntry>) -> Self {
+        Self {
+            title,
+            path: path.to_owned(),
+            page: page.clone(),
+            updated: entries.iter().map(|entry| entry.updated).max(),
+            entries,
+        }
+    }
+
+    /// Returns the absolute URL of the first page of the feed in the given format.
+    fn url<F: FeedFormat>(&self, config: &Config) -> String {
+        format!("{}{}.{}", config.base_url, self.path, F::EXTENSION)
+    }
+
+    /// Returns the absolute URL of the page of the feed in the given format with the entries after
+    /// the given cursor.
+    fn page_url<F: FeedFormat>(&self, cursor: &Cursor, config: &Config) -> String {
+        format!("{}?after={}", self.url::<F>(config), cursor)
+    }
+
+    /// Returns the absolute URL of the archive document of the feed in the given format with the
+    /// given number.
+    fn archive_url<F: FeedFormat>(&self, archive: usize, config: &Config) -> String {
+        format!("{}?archive={}", self.url::<F>(config), archive)
+    }
+
+    /// Returns the absolute URL of this page of the feed in the given format.
+    fn self_url<F: FeedFormat>(&self, config: &Config) -> String {
+        match (self.page.archive, &self.page.after) {
+            (Some(archive), _) => self.archive_url::<F>(archive, config),
+            (None, Some(after)) => self.page_url::<F>(after, config),
+            (None, None) => self.url::<F>(config),
+        }
+    }
+
+    /// Returns the number of the archive document with the entries just before this page, if
+    /// there is one.
+    fn prev_archive(&self) -> Option<usize> {
+        match self.page.archive {
+            Some(archive) => archive.checked_sub(1),
+            None if self.page.after.is_none() => self.page.archive_count.checked_sub(1),
+            None => None,
+        }
+    }
+
+    /// Returns the number of the archive document with the entries just after this one, if this is
+    /// an archive document and it isn't the newest.
+    fn next_archive(&self) -> Option<usize> {
+        self.page
+            .archive
+            .map(|archive| archive + 1)
+            .filter(|&next| next < self.page.archive_count)
+    }
+}
+
+/// Which page of a feed a document is, so that it can link to the other pages as described by
+/// RFC 5005.
+///
+/// The first page is the subscription document, with the most recent entries. From there, the
+/// older entries can be followed in two ways. The paged view goes back a page at a time from the
+/// newest entry, so its pages shift whenever an entry is added. The archive documents instead
+/// split the entries into pages counted from the oldest entry, so that adding entries doesn't
+/// change them. Only full pages are archived, so the entries newer than the newest archive
+/// document are always in the subscription document. The subscription document still has a full page
+/// of entries though, so it overlaps the newest archive document: it repeats all of its entries
+/// but the oldest few, or all of them when the number of entries is a multiple of the page size.
+/// Readers merge the repeated entries by their IDs.
+///
+/// The archive documents are cut from the entries which the viewer can see by counting, rather
+/// than by keeping the boundaries between them, so they are only stable while entries are added.
+/// When an entry older than the subscription document goes away, for example because it is
+/// deleted or hidden or its context is made private, every later entry moves back by one, so the
+/// archive documents from there on change, and the newest one may stop existing. A reader which
+/// has cached the older archive documents may then miss the entry which moved back into them.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FeedPage {
+    /// The cursor after which the entries of this page of the paged view start, or `None` for the
+    /// first page or an archive document.
+    pub after: Option<Cursor>,
+    /// The cursor for the next page of older entries in the paged view, if there are any.
+    pub next: Option<Cursor>,
+    /// The number of this archive document, counting from 0 for the oldest, if it is one.
+    pub archive: Option<usize>,
+    /// The number of archive documents the feed has.
+    pub archive_count: usize,
+}
+
+impl FeedPage {
+    /// Returns the page which the given query asks for of a feed with `total` entries, with
+    /// `per_page` entries in each page. `next` must be filled in separately if it isn't an archive
+    /// document.
+    pub fn new(
+        query: &QueryFeedPage,
+        total: usize,
+        per_page: usize,
+    ) -> Result<Self, InternalError> {
+        let archive_count = total / per_page;
+        if query
+            .archive
+            .is_some_and(|archive| archive >= archive_count)
+        {
+            return Err(InternalError::NotFound);
+        }
+        Ok(Self {
+            after: query.after.filter(|_| query.archive.is_none()),
+            next: None,
+            archive: query.archive,
+            archive_count,
+        })
+    }
+
+    /// If this is an archive document, returns which entries it has as a numbered page of the
+    /// feed's `total` entries from the newest, with `per_page` entries in each page.
+    pub fn archive_entries(&self, total: usize, per_page: usize) -> Option<(Pages, Page)> {
+        let start = total - (self.archive? + 1) * per_page;
+        Some((
+            Pages::new(total, per_page),
+            Page {
+                offset: start / per_page,
+                length: per_page,
+                start,
+                end: start + per_page,
+            },
+        ))
+    }
+}
+
+/// A quote or comment in a feed.
+#[derive(Clone, Debug)]
+pub struct FeedEntry {
+    pub title: String,
+    /// The absolute URL of the HTML page for the entry, which is also used as its ID.
+    pub url: String,
+    pub published: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub author_name: String,
+    /// The absolute URL of the author's page.
+    pub author_url: String,
+    /// The content of the entry, as HTML.
+    pub content: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub fn config() -> Config {
+        toml::from_str(
+            r#"
+            postgres_uri = "postgres://localhost/quotebook"
+            google_client_id = "abc123"
+            secret = "secret"
+            base_url = "https://quotes.example.com"
+            "#,
+        )
+        .unwrap()
+    }
+
+    pub fn cursor(id: i32) -> Cursor {
+        Cursor {
+            created_at: DateTime::from_timestamp(1_760_745_600, 0).unwrap(),
+            id,
+        }
+    }
+
+    pub fn entry(id: i32, updated: i64) -> FeedEntry {
+        FeedEntry {
+            title: format!("Someone: Quote {}", id),
+            url: format!("https://quotes.example.com/quotes/{}", id),
+            published: DateTime::from_timestamp(1_760_745_600, 0).unwrap(),
+            updated: DateTime::from_timestamp(updated, 0).unwrap(),
+            author_name: "someone".to_string(),
+            author_url: "https://quotes.example.com/users/1".to_string(),
+            content: "<p>Quote</p>".to_string(),
+        }
+    }
+
+    /// Returns the given page of a feed with two entries.
+    pub fn feed(page: FeedPage) -> Feed {
+        Feed::new(
+            "Quotes".to_string(),
+            "/quotes",
+            &page,
+            vec![entry(2, 1_760_832_000), entry(1, 1_760_918_400)],
+        )
+    }
+
+    #[test]
+    fn updated_is_latest_entry() {
+        let feed = feed(FeedPage::default());
+        assert_eq!(feed.updated, DateTime::from_timestamp(1_760_918_400, 0));
+        let empty = Feed::new(
+            "Quotes".to_string(),
+            "/quotes",
+            &FeedPage::default(),
+            vec![],
+        );
+        assert_eq!(empty.updated, None);
+    }
+
+    /// Returns the page of a feed with `total` entries and 10 entries in each page which the
+    /// given query asks for, if it exists.
+    fn page(after: Option<Cursor>, archive: Option<usize>, total: usize) -> Option<FeedPage> {
+        FeedPage::new(&QueryFeedPage { after, archive }, total, 10).ok()
+    }
+
+    #[test]
+    fn archives_are_counted_from_oldest() {
+        // With 25 entries in pages of 10, the oldest archive is the 10 entries before the newest
+        // 15, and the newest 5 aren't archived yet.
+        let oldest = page(None, Some(0), 25).unwrap();
+        assert_eq!(oldest.archive_count, 2);
+        let (pages, entries) = oldest.archive_entries(25, 10).unwrap();
+        assert_eq!((pages.limit(), entries.start), (10, 15));
+        // A new entry doesn't change which entries it has.
+        let (_, entries) = page(None, Some(0), 26)
+            .unwrap()
+            .archive_entries(26, 10)
+            .unwrap();
+        assert_eq!(entries.start, 16);
+
+        let (_, entries) = page(None, Some(1), 25)
+            .unwrap()
+            .archive_entries(25, 10)
+            .unwrap();
+        assert_eq!(entries.start, 5);
+        assert!(page(None, Some(1), 19).is_none());
+    }
+
+    #[test]
+    fn paged_view_is_not_an_archive() {
+        let paged = page(Some(cursor(42)), None, 25).unwrap();
+        assert_eq!(paged.after, Some(cursor(42)));
+        assert!(paged.archive_entries(25, 10).is_none());
+        // An archive takes precedence over a cursor.
+        assert_eq!(page(Some(cursor(42)), Some(0), 25).unwrap().after, None);
+    }
+}
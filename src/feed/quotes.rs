@@ -0,0 +1,37 @@
+use super::{Feed, FeedEntry, FeedFormat, FeedPage};
+use crate::{
+    config::Config, errors::InternalError, filters::chatty_quote, model::QuoteWithUsers,
+    responses::Validated,
+};
+use axum::http::HeaderMap;
+
+pub fn quotes_to_feed<F: FeedFormat>(
+    quotes: Vec<QuoteWithUsers>,
+    title: String,
+    path: &str,
+    page: &FeedPage,
+    config: &Config,
+    request_headers: &HeaderMap,
+) -> Result<Validated<F>, InternalError> {
+    // The feed also depends on its format and where it is, as well as on what is in it.
+    let data = (quotes, title, path, page, F::EXTENSION, &config.base_url);
+    Validated::new(data, request_headers, |(quotes, title, ..)| {
+        let entries = quotes
+            .into_iter()
+            .map(|quote| quote_entry(&config.base_url, quote))
+            .collect::<Result<Vec<_>, InternalError>>()?;
+        Ok(F::render(Feed::new(title, path, page, entries), config))
+    })
+}
+
+fn quote_entry(base_url: &str, quote: QuoteWithUsers) -> Result<FeedEntry, InternalError> {
+    Ok(FeedEntry {
+        title: format!("{}: {}", quote.quotee.fullname, quote.quote.quote_text),
+        url: format!("{}/quotes/{}", base_url, quote.quote.id),
+        published: quote.quote.created_at,
+        updated: quote.quote.updated_at,
+        author_name: quote.quoter.username_or_fullname().to_owned(),
+        author_url: format!("{}/users/{}", base_url, quote.quoter.id),
+        content: chatty_quote(quote, base_url)?,
+    })
+}
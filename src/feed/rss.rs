@@ -0,0 +1,163 @@
+use super::{Feed, FeedEntry, FeedFormat, FEED_HISTORY_NAMESPACE};
+use crate::{config::Config, responses::Rss};
+use atom_syndication::{Link, LinkBuilder};
+use rss::{
+    extension::{
+        atom::AtomExtensionBuilder, dublincore::DublinCoreExtensionBuilder, ExtensionBuilder,
+    },
+    ChannelBuilder, GuidBuilder, Item, ItemBuilder,
+};
+use std::collections::BTreeMap;
+
+impl FeedFormat for Rss {
+    const EXTENSION: &'static str = "rss";
+    const MIME_TYPE: &'static str = "application/rss+xml";
+
+    fn render(feed: Feed, config: &Config) -> Self {
+        let feed_url = feed.url::<Rss>(config);
+
+        // RSS has no links between the pages of a feed, so they are added as Atom links as RFC
+        // 5005 describes.
+        let mut links = vec![rss_link("self", feed.self_url::<Rss>(config))];
+        if let Some(next) = &feed.page.next {
+            links.push(rss_link("next", feed.page_url::<Rss>(next, config)));
+        }
+        if let Some(prev_archive) = feed.prev_archive() {
+            links.push(rss_link(
+                "prev-archive",
+                feed.archive_url::<Rss>(prev_archive, config),
+            ));
+        }
+        if let Some(next_archive) = feed.next_archive() {
+            links.push(rss_link(
+                "next-archive",
+                feed.archive_url::<Rss>(next_archive, config),
+            ));
+        }
+        if feed.page.after.is_some() {
+            links.push(rss_link("first", feed_url.clone()));
+        }
+
+        let mut builder = ChannelBuilder::default();
+        builder
+            .description(feed.title.clone())
+            .title(feed.title)
+            .link(config.absolute_url(&feed.path))
+            .last_build_date(feed.updated.map(|updated| updated.to_rfc2822()))
+            .generator("theQuotebook".to_string())
+            .items(feed.entries.into_iter().map(item).collect::<Vec<_>>());
+        if feed.page.archive.is_some() {
+            links.push(rss_link("current", feed_url));
+            builder
+                .namespace(("fh".to_string(), FEED_HISTORY_NAMESPACE.to_string()))
+                .extension((
+                    "fh".to_string(),
+                    BTreeMap::from([(
+                        "archive".to_string(),
+                        vec![ExtensionBuilder::default().name("fh:archive").build()],
+                    )]),
+                ));
+        }
+        builder.atom_ext(AtomExtensionBuilder::default().links(links).build());
+
+        Rss(builder.build())
+    }
+}
+
+/// Returns a link to another page of the same feed.
+fn rss_link(rel: &str, href: String) -> Link {
+    LinkBuilder::default()
+        .rel(rel)
+        .mime_type(Rss::MIME_TYPE.to_string())
+        .href(href)
+        .build()
+}
+
+fn item(entry: FeedEntry) -> Item {
+    ItemBuilder::default()
+        .title(entry.title)
+        .link(entry.url.clone())
+        .guid(
+            GuidBuilder::default()
+                .value(entry.url)
+                .permalink(true)
+                .build(),
+        )
+        .pub_date(entry.published.to_rfc2822())
+        // The RSS `author` element must be an email address, so the Dublin Core creator is used
+        // for the author's name instead.
+        .dublin_core_ext(
+            DublinCoreExtensionBuilder::default()
+                .creator(entry.author_name)
+                .build(),
+        )
+        .description(entry.content)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::{
+        tests::{config, cursor, feed},
+        FeedPage,
+    };
+
+    #[test]
+    fn subscription_document_links_to_older_entries() {
+        let channel = Rss::render(
+            feed(FeedPage {
+                next: Some(cursor(42)),
+                archive_count: 1,
+                ..FeedPage::default()
+            }),
+            &config(),
+        )
+        .0;
+        assert_eq!(channel.link, "https://quotes.example.com/quotes");
+        assert_eq!(
+            channel.last_build_date.as_deref(),
+            Some("Mon, 20 Oct 2025 00:00:00 +0000")
+        );
+        let links = channel
+            .atom_ext
+            .as_ref()
+            .unwrap()
+            .links
+            .iter()
+            .map(|link| (link.rel.as_str(), link.href.as_str()))
+            .collect::<Vec<_>>();
+        let older = format!("https://quotes.example.com/quotes.rss?after={}", cursor(42));
+        assert_eq!(
+            links,
+            vec![
+                ("self", "https://quotes.example.com/quotes.rss"),
+                ("next", older.as_str()),
+                (
+                    "prev-archive",
+                    "https://quotes.example.com/quotes.rss?archive=0"
+                ),
+            ]
+        );
+        let xml = channel.to_string();
+        assert!(xml.contains("<dc:creator>someone</dc:creator>"));
+        assert!(!xml.contains("fh:archive"));
+    }
+
+    #[test]
+    fn archive_document_is_marked() {
+        let xml = Rss::render(
+            feed(FeedPage {
+                archive: Some(0),
+                archive_count: 1,
+                ..FeedPage::default()
+            }),
+            &config(),
+        )
+        .0
+        .to_string();
+        assert!(xml.contains(r#"xmlns:fh="http://purl.org/syndication/history/1.0""#));
+        assert!(xml.contains("<fh:archive>"));
+        assert!(xml.contains(r#"rel="current""#));
+    }
+}
@@ -1,7 +1,8 @@
-mod atom;
+mod cli;
 mod config;
 mod controllers;
 mod errors;
+mod feed;
 mod filters;
 mod markdown;
 mod model;
@@ -11,15 +12,22 @@ mod session;
 
 use axum::{
     extract::Extension,
+    middleware,
     routing::{get, get_service, post},
     Router,
 };
+use clap::Parser;
+use cli::{Args, Command};
 use config::Config;
-use controllers::{comments, contexts, home, quotes, sessions, users};
+use controllers::{
+    admin, api, api_tokens, comments, contexts, home, invitations, notifications, quotes, search,
+    sessions, users,
+};
 use errors::internal_error;
 use eyre::Report;
 use log::info;
-use sqlx::postgres::PgPoolOptions;
+use responses::{Atom, JsonFeed, Rss};
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_cookies::CookieManagerLayer;
@@ -31,63 +39,229 @@ async fn main() -> Result<(), Report> {
     pretty_env_logger::init();
     color_backtrace::install();
 
+    let args = Args::parse();
     let config = Arc::new(Config::from_file()?);
 
-    let pool = PgPoolOptions::new()
+    match args.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            let pool = connect(&config).await?;
+            model::migrate(&pool).await?;
+            serve(config, pool).await
+        }
+        Command::Maintenance(command) => cli::run(command, &connect(&config).await?).await,
+        // This connects to the database itself, so that it can report if it can't.
+        Command::CheckConfig => cli::check_config(&config).await,
+    }
+}
+
+/// Connects to the database given in the config.
+async fn connect(config: &Config) -> Result<Pool<Postgres>, Report> {
+    Ok(PgPoolOptions::new()
         .max_connections(5)
         .connect(&config.postgres_uri)
-        .await?;
+        .await?)
+}
 
+/// Runs the web server until it fails.
+async fn serve(config: Arc<Config>, pool: Pool<Postgres>) -> Result<(), Report> {
     let app = Router::new()
         .route("/", get(home::index))
         .route("/login", get(sessions::new))
         .route("/logout", get(sessions::destroy))
         .route("/google_auth", post(sessions::google_auth))
+        .route(
+            "/admin/merge_users",
+            get(admin::merge_users_form).post(admin::merge_users),
+        )
+        .route("/api/openapi.json", get(api::openapi))
+        .route("/api/v1/comments", get(api::comments::index))
+        .route("/api/v1/contexts", get(api::contexts::index))
+        .route("/api/v1/contexts/{context_id}", get(api::contexts::show))
+        .route(
+            "/api/v1/contexts/{context_id}/quotes",
+            get(api::contexts::quotes),
+        )
+        .route(
+            "/api/v1/quotes",
+            get(api::quotes::index).post(api::quotes::create),
+        )
+        .route("/api/v1/quotes/{quote_id}", get(api::quotes::show))
+        .route(
+            "/api/v1/quotes/{quote_id}/comments",
+            get(api::comments::index_for_quote).post(api::comments::create),
+        )
+        .route(
+            "/api/v1/quotes/{quote_id}/comments/{comment_id}",
+            get(api::comments::show),
+        )
+        .route("/api/v1/search", get(api::search::index))
+        .route("/api/v1/users/{user_id}", get(api::users::show))
+        .route("/api/v1/users/{user_id}/quotes", get(api::users::quotes))
+        .route(
+            "/api/v1/users/{user_id}/relevant_quotes",
+            get(api::users::relevant_quotes),
+        )
+        .route(
+            "/api/v1/users/{user_id}/relevant_comments",
+            get(api::users::relevant_comments),
+        )
         .route("/comments", get(home::comments))
-        .route("/comments.atom", get(home::comments_atom))
-        .route("/contexts", get(contexts::index))
+        .route("/comments.atom", get(home::comments_feed::<Atom>))
+        .route("/comments.rss", get(home::comments_feed::<Rss>))
+        .route("/comments.json", get(home::comments_feed::<JsonFeed>))
+        .route("/contexts", get(contexts::index).post(contexts::create))
         .route("/contexts/new", get(contexts::new))
-        .route("/contexts/:context_id", get(contexts::show))
-        .route("/contexts/:context_id/edit", get(contexts::edit))
-        .route("/contexts/:context_id/latest", get(contexts::latest))
-        .route("/contexts/:context_id/join", post(contexts::join))
-        .route("/contexts/:context_id/leave", post(contexts::leave))
-        .route("/contexts/:context_id/quotes", get(contexts::quotes))
-        .route(
-            "/contexts/:context_id/quotes.atom",
-            get(contexts::quotes_atom),
-        )
-        .route("/users", get(users::index))
-        .route("/users/:user_id", get(users::show))
-        .route("/users/:user_id/quotes", get(users::quotes))
-        .route("/users/:user_id/quotes.atom", get(users::quotes_atom))
-        .route(
-            "/users/:user_id/relevant_quotes",
+        .route(
+            "/contexts/{context_id}",
+            get(contexts::show).post(contexts::update),
+        )
+        .route("/contexts/{context_id}/edit", get(contexts::edit))
+        .route("/contexts/{context_id}/latest", get(contexts::latest))
+        .route("/contexts/{context_id}/join", post(contexts::join))
+        .route("/contexts/{context_id}/leave", post(contexts::leave))
+        .route("/contexts/{context_id}/hidden", get(contexts::hidden))
+        .route(
+            "/contexts/{context_id}/invitations",
+            post(invitations::create),
+        )
+        .route(
+            "/contexts/{context_id}/invitations/{invitation_id}/revoke",
+            post(invitations::destroy),
+        )
+        .route(
+            "/contexts/{context_id}/members/{user_id}/remove",
+            post(contexts::remove_member),
+        )
+        .route(
+            "/contexts/{context_id}/members/{user_id}/role",
+            post(contexts::set_role),
+        )
+        .route("/contexts/{context_id}/quotes", get(contexts::quotes))
+        .route(
+            "/contexts/{context_id}/quotes.atom",
+            get(contexts::quotes_feed::<Atom>),
+        )
+        .route(
+            "/contexts/{context_id}/quotes.rss",
+            get(contexts::quotes_feed::<Rss>),
+        )
+        .route(
+            "/contexts/{context_id}/quotes.json",
+            get(contexts::quotes_feed::<JsonFeed>),
+        )
+        .route("/invitations/{token}", get(invitations::show))
+        .route("/notifications", get(notifications::index))
+        .route("/search", get(search::index))
+        .route("/users", get(users::index).post(users::create))
+        .route("/users/new", get(users::new))
+        .route("/users/{user_id}", get(users::show).post(users::update))
+        .route("/users/{user_id}/quotes", get(users::quotes))
+        .route(
+            "/users/{user_id}/quotes.atom",
+            get(users::quotes_feed::<Atom>),
+        )
+        .route(
+            "/users/{user_id}/quotes.rss",
+            get(users::quotes_feed::<Rss>),
+        )
+        .route(
+            "/users/{user_id}/quotes.json",
+            get(users::quotes_feed::<JsonFeed>),
+        )
+        .route(
+            "/users/{user_id}/relevant_quotes",
             get(users::relevant_quotes),
         )
         .route(
-            "/users/:user_id/relevant_quotes.atom",
-            get(users::relevant_quotes_atom),
+            "/users/{user_id}/relevant_quotes.atom",
+            get(users::relevant_quotes_feed::<Atom>),
+        )
+        .route(
+            "/users/{user_id}/relevant_quotes.rss",
+            get(users::relevant_quotes_feed::<Rss>),
         )
         .route(
-            "/users/:user_id/relevant_comments",
+            "/users/{user_id}/relevant_quotes.json",
+            get(users::relevant_quotes_feed::<JsonFeed>),
+        )
+        .route(
+            "/users/{user_id}/relevant_comments",
             get(users::relevant_comments),
         )
         .route(
-            "/users/:user_id/relevant_comments.atom",
-            get(users::relevant_comments_atom),
+            "/users/{user_id}/relevant_comments.atom",
+            get(users::relevant_comments_feed::<Atom>),
+        )
+        .route(
+            "/users/{user_id}/relevant_comments.rss",
+            get(users::relevant_comments_feed::<Rss>),
+        )
+        .route(
+            "/users/{user_id}/relevant_comments.json",
+            get(users::relevant_comments_feed::<JsonFeed>),
+        )
+        .route("/users/{user_id}/edit", get(users::edit))
+        .route(
+            "/users/{user_id}/email",
+            get(users::edit_email).post(users::update_email),
+        )
+        .route("/users/{user_id}/api_tokens", post(api_tokens::create))
+        .route(
+            "/users/{user_id}/api_tokens/{token_id}/revoke",
+            post(api_tokens::destroy),
         )
-        .route("/users/:user_id/edit", get(users::edit))
-        .route("/quotes", get(quotes::index))
-        .route("/quotes.atom", get(quotes::index_atom))
+        .route("/users/{user_id}/claim", post(users::claim))
+        .route("/users/{user_id}/vouch", post(users::vouch))
+        .route("/quotes", get(quotes::index).post(quotes::create))
+        .route("/quotes.atom", get(quotes::index_feed::<Atom>))
+        .route("/quotes.rss", get(quotes::index_feed::<Rss>))
+        .route("/quotes.json", get(quotes::index_feed::<JsonFeed>))
         .route("/quotes/new", get(quotes::new))
-        .route("/quotes/:quote_id", get(quotes::show))
-        .route("/quotes/:quote_id/edit", get(quotes::edit))
-        .route("/quotes/:quote_id/comments", get(comments::index))
-        .route("/quotes/:quote_id/comments.atom", get(comments::index_atom))
         .route(
-            "/quotes/:quote_id/comments/:comment_id",
-            get(comments::show),
+            "/quotes/{quote_id}",
+            get(quotes::show)
+                .post(quotes::update)
+                .put(quotes::update)
+                .delete(quotes::destroy),
+        )
+        .route("/quotes/{quote_id}/delete", post(quotes::destroy))
+        .route("/quotes/{quote_id}/hide", post(quotes::hide))
+        .route("/quotes/{quote_id}/unhide", post(quotes::unhide))
+        .route(
+            "/quotes/{quote_id}/request_removal",
+            post(quotes::request_removal),
+        )
+        .route("/quotes/{quote_id}/approve", post(quotes::approve))
+        .route("/quotes/{quote_id}/reject", post(quotes::reject))
+        .route("/quotes/{quote_id}/edit", get(quotes::edit))
+        .route(
+            "/quotes/{quote_id}/comments",
+            get(comments::index).post(comments::create),
+        )
+        .route(
+            "/quotes/{quote_id}/comments.atom",
+            get(comments::index_feed::<Atom>),
+        )
+        .route(
+            "/quotes/{quote_id}/comments.rss",
+            get(comments::index_feed::<Rss>),
+        )
+        .route(
+            "/quotes/{quote_id}/comments.json",
+            get(comments::index_feed::<JsonFeed>),
+        )
+        .route("/quotes/{quote_id}/comments/new", get(comments::new))
+        .route(
+            "/quotes/{quote_id}/comments/{comment_id}",
+            get(comments::show).post(comments::update),
+        )
+        .route(
+            "/quotes/{quote_id}/comments/{comment_id}/edit",
+            get(comments::edit),
+        )
+        .route(
+            "/quotes/{quote_id}/comments/{comment_id}/delete",
+            post(comments::destroy),
         )
         .nest_service(
             "/images",
@@ -99,6 +273,7 @@ async fn main() -> Result<(), Report> {
             get_service(ServeDir::new(config.public_dir.join("stylesheets")))
                 .handle_error(internal_error),
         )
+        .layer(middleware::from_fn(responses::conditional_get))
         .layer(CookieManagerLayer::new())
         .layer(Extension(config.clone()))
         .layer(Extension(pool));
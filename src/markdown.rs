@@ -35,15 +35,11 @@ fn to_html<'a>(
     let mut between_paragraphs = false;
     for event in events {
         match event {
-            Event::Start(tag) => {
-                if allowed(&tag, allowed_tags) {
-                    start(&mut result, &mut between_paragraphs, &tag, newlines_allowed)
-                }
+            Event::Start(tag) if allowed(&tag, allowed_tags) => {
+                start(&mut result, &mut between_paragraphs, &tag, newlines_allowed)
             }
-            Event::End(tag) => {
-                if allowed_end(&tag, allowed_tags) {
-                    end(&mut result, &mut between_paragraphs, &tag)
-                }
+            Event::End(tag) if allowed_end(&tag, allowed_tags) => {
+                end(&mut result, &mut between_paragraphs, &tag)
             }
             Event::Text(text) => escape_html(&mut result, &text).unwrap(),
             Event::Code(text) => {
@@ -83,10 +79,8 @@ fn start(buffer: &mut String, between_paragraphs: &mut bool, tag: &Tag, newlines
     match tag {
         Tag::Emphasis => *buffer += "<em>",
         Tag::Strong => *buffer += "<strong>",
-        Tag::Paragraph => {
-            if *between_paragraphs {
-                *buffer += if newlines_allowed { "<br/><br/>" } else { " " };
-            }
+        Tag::Paragraph if *between_paragraphs => {
+            *buffer += if newlines_allowed { "<br/><br/>" } else { " " };
         }
         Tag::Link {
             dest_url, title, ..
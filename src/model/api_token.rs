@@ -0,0 +1,103 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::{
+    types::chrono::{DateTime, Utc},
+    FromRow, Pool, Postgres,
+};
+
+/// The prefix of every API token, to make them easy to recognise.
+const TOKEN_PREFIX: &str = "tqb_";
+
+/// The number of random characters in an API token, after the prefix.
+const TOKEN_LENGTH: usize = 40;
+
+/// A personal token which lets a script use the API as the user who created it.
+#[derive(Clone, Debug, FromRow)]
+pub struct ApiToken {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    /// Fetches all API tokens of the given user, starting with the most recently created.
+    pub async fn fetch_all_for_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT id, name,
+               created_at AT TIME ZONE 'UTC' AS created_at,
+               last_used_at AT TIME ZONE 'UTC' AS last_used_at
+             FROM api_tokens
+             WHERE user_id = $1
+             ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Adds a new API token with a random value for the given user, returning the token. This is
+    /// the only time the token is available, as only its hash is stored.
+    pub async fn create(pool: &Pool<Postgres>, user_id: i32, name: &str) -> sqlx::Result<String> {
+        let random: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        let token = format!("{}{}", TOKEN_PREFIX, random);
+        sqlx::query(
+            "INSERT INTO api_tokens (user_id, name, token_hash, created_at)
+             VALUES ($1, $2, $3, NOW() AT TIME ZONE 'UTC')",
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(hash_token(&token))
+        .execute(pool)
+        .await?;
+        Ok(token)
+    }
+
+    /// Revokes the given API token of the given user, returning whether it existed.
+    pub async fn delete(pool: &Pool<Postgres>, user_id: i32, token_id: i32) -> sqlx::Result<bool> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE user_id = $1 AND id = $2")
+            .bind(user_id)
+            .bind(token_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns the ID of the user whose API token the given token is, or `None` if it isn't a
+    /// valid token, and records that the token was used.
+    pub async fn use_token(pool: &Pool<Postgres>, token: &str) -> sqlx::Result<Option<i32>> {
+        sqlx::query_scalar::<_, i32>(
+            "UPDATE api_tokens SET last_used_at = NOW() AT TIME ZONE 'UTC'
+             WHERE token_hash = $1
+             RETURNING user_id",
+        )
+        .bind(hash_token(token))
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+/// Returns the hex-encoded SHA-256 hash of the given token, which is what is stored.
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_hex_sha256() {
+        assert_eq!(
+            hash_token("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
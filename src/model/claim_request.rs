@@ -0,0 +1,61 @@
+use sqlx::{FromRow, Pool, Postgres};
+
+/// A request from someone who has logged in with Google to become a placeholder user, waiting for
+/// someone who can vouch for the placeholder user to confirm it.
+#[derive(Clone, Debug, FromRow)]
+pub struct ClaimRequest {
+    /// The verified email address which the person logged in with.
+    pub email_address: String,
+    /// The name which the person has on their Google account.
+    pub fullname: String,
+}
+
+impl ClaimRequest {
+    /// Fetches all outstanding requests to claim the given placeholder user, oldest first.
+    pub async fn fetch_all_for_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT email_address, fullname
+             FROM claim_requests
+             WHERE user_id = $1
+             ORDER BY created_at ASC, id ASC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Adds a request for the person with the given email address to claim the given placeholder
+    /// user.
+    ///
+    /// Returns false if they had already asked.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        email_address: &str,
+        fullname: &str,
+    ) -> sqlx::Result<bool> {
+        let result = sqlx::query(
+            "INSERT INTO claim_requests (user_id, email_address, fullname, created_at)
+             VALUES ($1, $2, $3, NOW() AT TIME ZONE 'UTC')
+             ON CONFLICT (user_id, email_address) DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(email_address)
+        .bind(fullname)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Removes all requests to claim the given user, once they have been claimed.
+    pub async fn delete_all_for_user(pool: &Pool<Postgres>, user_id: i32) -> sqlx::Result<()> {
+        sqlx::query("DELETE FROM claim_requests WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
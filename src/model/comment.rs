@@ -1,6 +1,10 @@
 use super::context::Context;
 use super::user::User;
-use crate::errors::InternalError;
+use crate::{
+    errors::InternalError,
+    pagination::{Cursor, HasCursor},
+};
+use paginate::{Page, Pages};
 use sqlx::{
     postgres::PgRow,
     types::chrono::{DateTime, Utc},
@@ -17,6 +21,76 @@ pub struct Comment {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Comment {
+    /// Fetches the comment with the given ID, if it exists, is for the given quote and the
+    /// given viewer may see it.
+    pub async fn fetch_one(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        comment_id: i32,
+        viewer_id: Option<i32>,
+    ) -> Result<Self, InternalError> {
+        sqlx::query_as::<_, Self>(
+            "SELECT comments.*,
+               comments.created_at AT TIME ZONE 'UTC' AS created_at,
+               comments.updated_at AT TIME ZONE 'UTC' AS updated_at
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+             WHERE comments.quote_id = $1 AND comments.id = $2
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($3))
+             AND NOT quotes.hidden",
+        )
+        .bind(quote_id)
+        .bind(comment_id)
+        .bind(viewer_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(InternalError::NotFound)
+    }
+
+    /// Adds a new comment on the given quote, returning its ID.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        user_id: i32,
+        body: &str,
+    ) -> sqlx::Result<i32> {
+        sqlx::query_scalar::<_, i32>(
+            "INSERT INTO comments (quote_id, user_id, body, created_at, updated_at)
+             VALUES ($1, $2, $3, NOW() AT TIME ZONE 'UTC', NOW() AT TIME ZONE 'UTC')
+             RETURNING id",
+        )
+        .bind(quote_id)
+        .bind(user_id)
+        .bind(body)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Updates the body of the given comment, marking it as updated now.
+    pub async fn update(pool: &Pool<Postgres>, comment_id: i32, body: &str) -> sqlx::Result<()> {
+        sqlx::query(
+            "UPDATE comments
+             SET body = $2, updated_at = NOW() AT TIME ZONE 'UTC'
+             WHERE id = $1",
+        )
+        .bind(comment_id)
+        .bind(body)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the given comment.
+    pub async fn delete(pool: &Pool<Postgres>, comment_id: i32) -> sqlx::Result<()> {
+        sqlx::query("DELETE FROM comments WHERE id = $1")
+            .bind(comment_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct CommentWithQuote {
     pub comment: Comment,
@@ -26,11 +100,13 @@ pub struct CommentWithQuote {
 }
 
 impl CommentWithQuote {
-    /// Fetches the comment with the given ID, if it exists and is for the given quote.
+    /// Fetches the comment with the given ID, if it exists, is for the given quote and the
+    /// given viewer may see it.
     pub async fn fetch_one(
         pool: &Pool<Postgres>,
         quote_id: i32,
         comment_id: i32,
+        viewer_id: Option<i32>,
     ) -> Result<Self, InternalError> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
@@ -43,16 +119,20 @@ impl CommentWithQuote {
                users.fullname AS user_fullname,
                users.openid AS user_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM comments
                INNER JOIN quotes ON quotes.id = comments.quote_id
                INNER JOIN users ON users.id = comments.user_id
                INNER JOIN contexts ON contexts.id = quotes.context_id
              WHERE comments.quote_id = $1
-               AND comments.id = $2",
+               AND comments.id = $2
+               AND quotes.context_id IN (SELECT * FROM visible_context_ids($3))
+               AND NOT quotes.hidden",
         )
         .bind(quote_id)
         .bind(comment_id)
+        .bind(viewer_id)
         .fetch_optional(pool)
         .await?
         .ok_or(InternalError::NotFound)
@@ -62,6 +142,7 @@ impl CommentWithQuote {
     pub async fn fetch_all_for_quote(
         pool: &Pool<Postgres>,
         quote_id: i32,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
@@ -74,21 +155,129 @@ impl CommentWithQuote {
                users.fullname AS user_fullname,
                users.openid AS user_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM comments
                INNER JOIN quotes ON quotes.id = comments.quote_id
                INNER JOIN users ON users.id = comments.user_id
                INNER JOIN contexts ON contexts.id = quotes.context_id
              WHERE comments.quote_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))
+             AND NOT quotes.hidden
              ORDER BY comments.created_at ASC",
         )
         .bind(quote_id)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns the number of comments for the given quote.
+    pub async fn count_for_quote(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<usize> {
+        Ok(sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*)
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+             WHERE comments.quote_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))
+             AND NOT quotes.hidden",
+        )
+        .bind(quote_id)
+        .bind(viewer_id)
+        .fetch_one(pool)
+        .await? as usize)
+    }
+
+    /// Fetches comments for the given quote, within the given page.
+    pub async fn fetch_page_for_quote(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        pages: &Pages,
+        page: &Page,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT comments.*,
+               comments.created_at AT TIME ZONE 'UTC' AS created_at,
+               comments.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               quotes.quote_text,
+               quotes.context_id,
+               users.email_address AS user_email_address,
+               users.username AS user_username,
+               users.fullname AS user_fullname,
+               users.openid AS user_openid,
+               contexts.name AS context_name,
+               contexts.description AS context_description,
+               contexts.private AS context_private
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+               INNER JOIN users ON users.id = comments.user_id
+               INNER JOIN contexts ON contexts.id = quotes.context_id
+             WHERE comments.quote_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             AND NOT quotes.hidden
+             ORDER BY comments.created_at ASC, comments.id ASC
+             LIMIT $2 OFFSET $3",
+        )
+        .bind(quote_id)
+        .bind(pages.limit() as i64)
+        .bind(page.start as i64)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Fetches up to `limit` comments for the given quote, after the given cursor if any.
+    pub async fn fetch_after_for_quote(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT comments.*,
+               comments.created_at AT TIME ZONE 'UTC' AS created_at,
+               comments.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               quotes.quote_text,
+               quotes.context_id,
+               users.email_address AS user_email_address,
+               users.username AS user_username,
+               users.fullname AS user_fullname,
+               users.openid AS user_openid,
+               contexts.name AS context_name,
+               contexts.description AS context_description,
+               contexts.private AS context_private
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+               INNER JOIN users ON users.id = comments.user_id
+               INNER JOIN contexts ON contexts.id = quotes.context_id
+             WHERE comments.quote_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($5))
+             AND NOT quotes.hidden
+             AND ($2::TIMESTAMP IS NULL OR (comments.created_at, comments.id) > ($2, $3))
+             ORDER BY comments.created_at ASC, comments.id ASC
+             LIMIT $4",
+        )
+        .bind(quote_id)
+        .bind(after.map(Cursor::created_at_utc))
+        .bind(after.map(|after| after.id))
+        .bind(limit as i64)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
 
     /// Fetches the 5 most recent comments made by the given user.
-    pub async fn fetch_5_for_user(pool: &Pool<Postgres>, user_id: i32) -> sqlx::Result<Vec<Self>> {
+    pub async fn fetch_5_for_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
                comments.created_at AT TIME ZONE 'UTC' AS created_at,
@@ -100,16 +289,20 @@ impl CommentWithQuote {
                users.fullname AS user_fullname,
                users.openid AS user_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM comments
                INNER JOIN quotes ON quotes.id = comments.quote_id
                INNER JOIN users ON users.id = comments.user_id
                INNER JOIN contexts ON contexts.id = quotes.context_id
              WHERE comments.user_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))
+             AND NOT quotes.hidden
              ORDER BY comments.created_at DESC
              LIMIT 5",
         )
         .bind(user_id)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
@@ -118,6 +311,7 @@ impl CommentWithQuote {
     pub async fn fetch_5_for_context(
         pool: &Pool<Postgres>,
         context_id: i32,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
@@ -130,16 +324,20 @@ impl CommentWithQuote {
                users.fullname AS user_fullname,
                users.openid AS user_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM comments
                INNER JOIN quotes ON quotes.id = comments.quote_id
                INNER JOIN users ON users.id = comments.user_id
                INNER JOIN contexts ON contexts.id = quotes.context_id
              WHERE quotes.context_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))
+             AND NOT quotes.hidden
              ORDER BY comments.created_at DESC
              LIMIT 5",
         )
         .bind(context_id)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
@@ -149,6 +347,7 @@ impl CommentWithQuote {
     pub async fn fetch_5_for_user_contexts(
         pool: &Pool<Postgres>,
         user_id: i32,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
@@ -161,17 +360,21 @@ impl CommentWithQuote {
                users.fullname AS user_fullname,
                users.openid AS user_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM comments
                INNER JOIN quotes ON quotes.id = comments.quote_id
                INNER JOIN users ON users.id = comments.user_id
                INNER JOIN contexts ON contexts.id = quotes.context_id
                INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
              WHERE contexts_users.user_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))
+             AND NOT quotes.hidden
              ORDER BY comments.created_at DESC
              LIMIT 5",
         )
         .bind(user_id)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
@@ -193,12 +396,22 @@ impl<'r> FromRow<'r, PgRow> for CommentWithQuote {
                 id: row.try_get("context_id")?,
                 name: row.try_get("context_name")?,
                 description: row.try_get("context_description")?,
+                private: row.try_get("context_private")?,
                 quotes_count: 0,
             },
         })
     }
 }
 
+impl HasCursor for CommentWithQuote {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.comment.created_at,
+            id: self.comment.id,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct CommentWithQuotee {
     pub comment: Comment,
@@ -208,8 +421,27 @@ pub struct CommentWithQuotee {
 }
 
 impl CommentWithQuotee {
-    /// Fetches all comments, starting with the most recently added.
-    pub async fn fetch_all(pool: &Pool<Postgres>) -> sqlx::Result<Vec<Self>> {
+    /// Returns the number of comments.
+    pub async fn count(pool: &Pool<Postgres>, viewer_id: Option<i32>) -> sqlx::Result<usize> {
+        Ok(sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*)
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+             WHERE quotes.context_id IN (SELECT * FROM visible_context_ids($1))
+             AND NOT quotes.hidden",
+        )
+        .bind(viewer_id)
+        .fetch_one(pool)
+        .await? as usize)
+    }
+
+    /// Fetches comments within the given page, starting with the most recently added.
+    pub async fn fetch_page(
+        pool: &Pool<Postgres>,
+        pages: &Pages,
+        page: &Page,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
                comments.created_at AT TIME ZONE 'UTC' AS created_at,
@@ -228,16 +460,66 @@ impl CommentWithQuotee {
                INNER JOIN quotes ON quotes.id = comments.quote_id
                INNER JOIN users ON users.id = comments.user_id
                INNER JOIN users AS quotee ON quotee.id = quotes.quotee_id
-             ORDER BY comments.created_at DESC",
+             WHERE quotes.context_id IN (SELECT * FROM visible_context_ids($3))
+             AND NOT quotes.hidden
+             ORDER BY comments.created_at DESC, comments.id DESC
+             LIMIT $1 OFFSET $2",
         )
+        .bind(pages.limit() as i64)
+        .bind(page.start as i64)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
 
-    /// Fetches all comments for the given quote.
-    pub async fn fetch_all_for_quote(
+    /// Fetches up to `limit` comments, starting with the most recently added, after the given cursor
+    /// if any.
+    pub async fn fetch_after(
+        pool: &Pool<Postgres>,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT comments.*,
+               comments.created_at AT TIME ZONE 'UTC' AS created_at,
+               comments.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               quotes.quote_text,
+               quotes.quotee_id,
+               users.email_address AS user_email_address,
+               users.username AS user_username,
+               users.fullname AS user_fullname,
+               users.openid AS user_openid,
+               quotee.username AS quotee_username,
+               quotee.fullname AS quotee_fullname,
+               quotee.email_address AS quotee_email_address,
+               quotee.openid AS quotee_openid
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+               INNER JOIN users ON users.id = comments.user_id
+               INNER JOIN users AS quotee ON quotee.id = quotes.quotee_id
+             WHERE quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             AND NOT quotes.hidden
+             AND ($1::TIMESTAMP IS NULL OR (comments.created_at, comments.id) < ($1, $2))
+             ORDER BY comments.created_at DESC, comments.id DESC
+             LIMIT $3",
+        )
+        .bind(after.map(Cursor::created_at_utc))
+        .bind(after.map(|after| after.id))
+        .bind(limit as i64)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Fetches comments for the given quote within the given page, starting with the most recently
+    /// added.
+    pub async fn fetch_page_for_quote(
         pool: &Pool<Postgres>,
         quote_id: i32,
+        pages: &Pages,
+        page: &Page,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
@@ -258,17 +540,91 @@ impl CommentWithQuotee {
                INNER JOIN users ON users.id = comments.user_id
                INNER JOIN users AS quotee ON quotee.id = quotes.quotee_id
              WHERE comments.quote_id = $1
-             ORDER BY comments.created_at ASC",
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             AND NOT quotes.hidden
+             ORDER BY comments.created_at DESC, comments.id DESC
+             LIMIT $2 OFFSET $3",
+        )
+        .bind(quote_id)
+        .bind(pages.limit() as i64)
+        .bind(page.start as i64)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Fetches up to `limit` comments for the given quote, starting with the most recently added,
+    /// after the given cursor if any.
+    pub async fn fetch_after_for_quote(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT comments.*,
+               comments.created_at AT TIME ZONE 'UTC' AS created_at,
+               comments.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               quotes.quote_text,
+               quotes.quotee_id,
+               users.email_address AS user_email_address,
+               users.username AS user_username,
+               users.fullname AS user_fullname,
+               users.openid AS user_openid,
+               quotee.username AS quotee_username,
+               quotee.fullname AS quotee_fullname,
+               quotee.email_address AS quotee_email_address,
+               quotee.openid AS quotee_openid
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+               INNER JOIN users ON users.id = comments.user_id
+               INNER JOIN users AS quotee ON quotee.id = quotes.quotee_id
+             WHERE comments.quote_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($5))
+             AND NOT quotes.hidden
+             AND ($2::TIMESTAMP IS NULL OR (comments.created_at, comments.id) < ($2, $3))
+             ORDER BY comments.created_at DESC, comments.id DESC
+             LIMIT $4",
         )
         .bind(quote_id)
+        .bind(after.map(Cursor::created_at_utc))
+        .bind(after.map(|after| after.id))
+        .bind(limit as i64)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
 
-    /// Fetches all comments on quotes in contexts of which the given user is a member.
-    pub async fn fetch_all_for_user_contexts(
+    /// Returns the number of comments on quotes in contexts of which the given user is a member.
+    pub async fn count_for_user_contexts(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<usize> {
+        Ok(sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*)
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+               INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
+             WHERE contexts_users.user_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))
+             AND NOT quotes.hidden",
+        )
+        .bind(user_id)
+        .bind(viewer_id)
+        .fetch_one(pool)
+        .await? as usize)
+    }
+
+    /// Fetches comments on quotes in contexts of which the given user is a member, within the given
+    /// page.
+    pub async fn fetch_page_for_user_contexts(
         pool: &Pool<Postgres>,
         user_id: i32,
+        pages: &Pages,
+        page: &Page,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT comments.*,
@@ -290,9 +646,59 @@ impl CommentWithQuotee {
                INNER JOIN users AS quotee ON quotee.id = quotes.quotee_id
                INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
              WHERE contexts_users.user_id = $1
-             ORDER BY comments.created_at DESC",
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             AND NOT quotes.hidden
+             ORDER BY comments.created_at DESC, comments.id DESC
+             LIMIT $2 OFFSET $3",
         )
         .bind(user_id)
+        .bind(pages.limit() as i64)
+        .bind(page.start as i64)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Fetches up to `limit` comments on quotes in contexts of which the given user is a member,
+    /// after the given cursor if any.
+    pub async fn fetch_after_for_user_contexts(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT comments.*,
+               comments.created_at AT TIME ZONE 'UTC' AS created_at,
+               comments.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               quotes.quote_text,
+               quotes.quotee_id,
+               users.email_address AS user_email_address,
+               users.username AS user_username,
+               users.fullname AS user_fullname,
+               users.openid AS user_openid,
+               quotee.username AS quotee_username,
+               quotee.fullname AS quotee_fullname,
+               quotee.email_address AS quotee_email_address,
+               quotee.openid AS quotee_openid
+             FROM comments
+               INNER JOIN quotes ON quotes.id = comments.quote_id
+               INNER JOIN users ON users.id = comments.user_id
+               INNER JOIN users AS quotee ON quotee.id = quotes.quotee_id
+               INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
+             WHERE contexts_users.user_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($5))
+             AND NOT quotes.hidden
+             AND ($2::TIMESTAMP IS NULL OR (comments.created_at, comments.id) < ($2, $3))
+             ORDER BY comments.created_at DESC, comments.id DESC
+             LIMIT $4",
+        )
+        .bind(user_id)
+        .bind(after.map(Cursor::created_at_utc))
+        .bind(after.map(|after| after.id))
+        .bind(limit as i64)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
@@ -320,3 +726,12 @@ impl<'r> FromRow<'r, PgRow> for CommentWithQuotee {
         })
     }
 }
+
+impl HasCursor for CommentWithQuotee {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.comment.created_at,
+            id: self.comment.id,
+        }
+    }
+}
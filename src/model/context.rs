@@ -6,59 +6,152 @@ pub struct Context {
     pub id: i32,
     pub name: String,
     pub description: String,
+    /// Whether the context's quotes and comments are only shown to its members.
+    pub private: bool,
     pub quotes_count: i64,
 }
 
 impl Context {
-    /// Fetches the context with the given ID, if it exists.
-    pub async fn fetch_one(pool: &Pool<Postgres>, context_id: i32) -> Result<Self, InternalError> {
+    /// The unique index on context names, ignoring case.
+    pub const NAME_INDEX: &str = "index_contexts_on_lower_name";
+
+    /// Fetches the context with the given ID, if it exists and the given viewer may see it.
+    pub async fn fetch_one(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        viewer_id: Option<i32>,
+    ) -> Result<Self, InternalError> {
         sqlx::query_as::<_, Self>(
             "SELECT contexts.*,
-               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id) as quotes_count
-            FROM contexts WHERE id = $1",
+               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id AND NOT hidden)
+                 AS quotes_count
+             FROM contexts
+             WHERE id = $1
+               AND contexts.id IN (SELECT * FROM visible_context_ids($2))",
         )
         .bind(context_id)
+        .bind(viewer_id)
         .fetch_optional(pool)
         .await?
         .ok_or(InternalError::NotFound)
     }
 
-    /// Fetches the top 5 contexts with the most quotes.
-    pub async fn fetch_top_5(pool: &Pool<Postgres>) -> sqlx::Result<Vec<Self>> {
+    /// Fetches the context with the given name, or `None`. The comparison is case-insensitive.
+    pub async fn fetch_by_name(
+        pool: &Pool<Postgres>,
+        name: &str,
+    ) -> Result<Option<Self>, InternalError> {
+        Ok(sqlx::query_as::<_, Self>(
+            "SELECT contexts.*,
+               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id AND NOT hidden)
+                 AS quotes_count
+            FROM contexts WHERE LOWER(name) = LOWER($1)",
+        )
+        .bind(name)
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    /// Fetches the top 5 contexts with the most quotes which the given viewer may see.
+    pub async fn fetch_top_5(
+        pool: &Pool<Postgres>,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT contexts.*,
-               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id) AS quotes_count
+               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id AND NOT hidden)
+                 AS quotes_count
              FROM contexts
+             WHERE contexts.id IN (SELECT * FROM visible_context_ids($1))
              ORDER BY quotes_count DESC LIMIT 5",
         )
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
 
-    /// Fetches all contexts, starting with the most recently created.
-    pub async fn fetch_all(pool: &Pool<Postgres>) -> sqlx::Result<Vec<Self>> {
+    /// Fetches all contexts which the given viewer may see, starting with the most recently
+    /// created.
+    pub async fn fetch_all(
+        pool: &Pool<Postgres>,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT contexts.*,
-               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id) AS quotes_count
+               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id AND NOT hidden)
+                 AS quotes_count
              FROM contexts
+             WHERE contexts.id IN (SELECT * FROM visible_context_ids($1))
              ORDER BY contexts.created_at DESC",
         )
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
 
-    /// Fetches all contexts of which the given user is a member.
-    pub async fn fetch_for_user(pool: &Pool<Postgres>, user_id: i32) -> sqlx::Result<Vec<Self>> {
+    /// Fetches all contexts of which the given user is a member, and which the given viewer may
+    /// see.
+    pub async fn fetch_for_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT contexts.*,
-               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id) AS quotes_count
+               (SELECT COUNT(*) FROM quotes WHERE quotes.context_id = contexts.id AND NOT hidden)
+                 AS quotes_count
              FROM contexts
                INNER JOIN contexts_users ON context_id = contexts.id
              WHERE user_id = $1
+               AND contexts.id IN (SELECT * FROM visible_context_ids($2))
              ORDER BY contexts.created_at DESC",
         )
         .bind(user_id)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
+
+    /// Adds a new context, returning its ID. Fails with a violation of [`Self::NAME_INDEX`] if
+    /// another context already has the name.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        name: &str,
+        description: &str,
+        private: bool,
+    ) -> sqlx::Result<i32> {
+        sqlx::query_scalar::<_, i32>(
+            "INSERT INTO contexts (name, description, private, created_at, updated_at)
+             VALUES ($1, $2, $3, NOW() AT TIME ZONE 'UTC', NOW() AT TIME ZONE 'UTC')
+             RETURNING id",
+        )
+        .bind(name)
+        .bind(description)
+        .bind(private)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Updates the name, description and privacy of the given context. Fails with a violation of
+    /// [`Self::NAME_INDEX`] if another context already has the name.
+    pub async fn update(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        name: &str,
+        description: &str,
+        private: bool,
+    ) -> sqlx::Result<()> {
+        sqlx::query(
+            "UPDATE contexts
+             SET name = $2, description = $3, private = $4, updated_at = NOW() AT TIME ZONE 'UTC'
+             WHERE id = $1",
+        )
+        .bind(context_id)
+        .bind(name)
+        .bind(description)
+        .bind(private)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
 }
@@ -0,0 +1,127 @@
+use super::member::ContextRole;
+use rand::{distributions::Alphanumeric, Rng};
+use sqlx::{
+    types::chrono::{DateTime, Utc},
+    FromRow, Pool, Postgres,
+};
+
+/// The number of random characters in an invitation token.
+const TOKEN_LENGTH: usize = 32;
+
+/// A link which lets whoever visits it join a context.
+#[derive(Clone, Debug, FromRow)]
+pub struct Invitation {
+    pub id: i32,
+    pub token: String,
+    pub single_use: bool,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Invitation {
+    /// Fetches all invitations to the given context which haven't yet expired, starting with the
+    /// one which expires soonest.
+    pub async fn fetch_outstanding_for_context(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT id, token, single_use, expires_at AT TIME ZONE 'UTC' AS expires_at
+             FROM invitations
+             WHERE context_id = $1 AND expires_at > NOW() AT TIME ZONE 'UTC'
+             ORDER BY expires_at ASC",
+        )
+        .bind(context_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Adds a new invitation to the given context with a random token, valid for the given number
+    /// of days.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        created_by: i32,
+        single_use: bool,
+        valid_days: i32,
+    ) -> sqlx::Result<Self> {
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        sqlx::query_as::<_, Self>(
+            "INSERT INTO invitations
+               (context_id, token, created_by, single_use, created_at, expires_at)
+             VALUES
+               ($1, $2, $3, $4, NOW() AT TIME ZONE 'UTC',
+                NOW() AT TIME ZONE 'UTC' + make_interval(days => $5))
+             RETURNING id, token, single_use, expires_at AT TIME ZONE 'UTC' AS expires_at",
+        )
+        .bind(context_id)
+        .bind(token)
+        .bind(created_by)
+        .bind(single_use)
+        .bind(valid_days)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Revokes the given invitation to the given context, returning whether it existed.
+    pub async fn delete(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        invitation_id: i32,
+    ) -> sqlx::Result<bool> {
+        let result = sqlx::query("DELETE FROM invitations WHERE context_id = $1 AND id = $2")
+            .bind(context_id)
+            .bind(invitation_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Makes the given user a member of the context which the invitation with the given token is
+    /// for, using up the invitation if it is single-use and they weren't already a member.
+    ///
+    /// Returns the ID of the context, or `None` if there is no such invitation or it has expired.
+    pub async fn accept(
+        pool: &Pool<Postgres>,
+        token: &str,
+        user_id: i32,
+    ) -> sqlx::Result<Option<i32>> {
+        let mut transaction = pool.begin().await?;
+        let Some((invitation_id, context_id, single_use)) = sqlx::query_as::<_, (i32, i32, bool)>(
+            "SELECT id, context_id, single_use
+                 FROM invitations
+                 WHERE token = $1 AND expires_at > NOW() AT TIME ZONE 'UTC'
+                 FOR UPDATE",
+        )
+        .bind(token)
+        .fetch_optional(&mut *transaction)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let joined = sqlx::query(
+            "INSERT INTO contexts_users (user_id, context_id, role)
+             VALUES ($1, $2, $3)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(context_id)
+        .bind(ContextRole::Member)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected()
+            > 0;
+        // Don't use up a single-use invitation on someone who was already a member.
+        if single_use && joined {
+            sqlx::query("DELETE FROM invitations WHERE id = $1")
+                .bind(invitation_id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(Some(context_id))
+    }
+}
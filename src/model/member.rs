@@ -0,0 +1,137 @@
+use super::user::User;
+use serde::Deserialize;
+use sqlx::{postgres::PgRow, FromRow, Pool, Postgres, Row};
+use std::fmt::{self, Display, Formatter};
+
+/// The role of a member of a context, which determines what they may do to it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum ContextRole {
+    /// May do anything an admin can, and also change the roles of other members. Each context has
+    /// exactly one owner.
+    Owner,
+    /// May edit the context, remove members and moderate quotes.
+    Admin,
+    /// May add quotes and comments.
+    Member,
+}
+
+impl ContextRole {
+    /// Returns whether this role may edit the context and moderate its members and quotes.
+    pub fn can_manage(self) -> bool {
+        self <= Self::Admin
+    }
+
+    /// Fetches the role of the given user in the given context, or `None` if they aren't a member.
+    pub async fn fetch(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        user_id: i32,
+    ) -> sqlx::Result<Option<Self>> {
+        sqlx::query_scalar::<_, Self>(
+            "SELECT role FROM contexts_users WHERE context_id = $1 AND user_id = $2",
+        )
+        .bind(context_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+impl Display for ContextRole {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Owner => "owner",
+            Self::Admin => "admin",
+            Self::Member => "member",
+        })
+    }
+}
+
+/// A user along with their role in some context.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Member {
+    pub user: User,
+    pub role: ContextRole,
+}
+
+impl Member {
+    /// Fetches all members of the given context, starting with the owner then admins.
+    pub async fn fetch_all_for_context(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT users.*, contexts_users.role
+             FROM users
+               INNER JOIN contexts_users ON user_id = users.id
+             WHERE context_id = $1
+             ORDER BY
+               CASE contexts_users.role WHEN 'owner' THEN 0 WHEN 'admin' THEN 1 ELSE 2 END,
+               users.created_at DESC",
+        )
+        .bind(context_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Changes the role of the given member of the given context, returning whether they are a
+    /// member.
+    ///
+    /// A context only has one owner, so making someone the owner makes the previous owner an
+    /// admin. The owner's role mustn't be changed to anything else, as that would leave the context
+    /// without one.
+    pub async fn set_role(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        user_id: i32,
+        role: ContextRole,
+    ) -> sqlx::Result<bool> {
+        let mut transaction = pool.begin().await?;
+        let result = sqlx::query(
+            "UPDATE contexts_users SET role = $3 WHERE context_id = $1 AND user_id = $2",
+        )
+        .bind(context_id)
+        .bind(user_id)
+        .bind(role)
+        .execute(&mut *transaction)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        if role == ContextRole::Owner {
+            sqlx::query(
+                "UPDATE contexts_users SET role = 'admin'
+                 WHERE context_id = $1 AND user_id != $2 AND role = 'owner'",
+            )
+            .bind(context_id)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(true)
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for Member {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Member {
+            user: User::from_row(row)?,
+            role: row.try_get("role")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_owners_and_admins_can_manage() {
+        assert!(ContextRole::Owner.can_manage());
+        assert!(ContextRole::Admin.can_manage());
+        assert!(!ContextRole::Member.can_manage());
+    }
+}
@@ -1,9 +1,102 @@
+//! Database models.
+//!
+//! Methods which fetch quotes, comments or contexts take the ID of the user viewing them, if any,
+//! and only return things in public contexts or in private contexts of which the viewer is a
+//! member. The queries filter by the `visible_context_ids` database function for this, so that the
+//! rule is only written once.
+
+mod api_token;
+mod claim_request;
 mod comment;
 mod context;
+mod invitation;
+mod member;
+mod notification;
 mod quote;
 mod user;
 
+pub use api_token::ApiToken;
+pub use claim_request::ClaimRequest;
 pub use comment::{Comment, CommentWithQuote, CommentWithQuotee};
 pub use context::Context;
-pub use quote::{Quote, QuoteWithUsers};
+pub use invitation::Invitation;
+pub use member::{ContextRole, Member};
+pub use notification::Notification;
+pub use quote::{Quote, QuoteSearch, QuoteWithUsers};
 pub use user::User;
+
+use sqlx::{migrate::MigrateError, Pool, Postgres};
+
+/// Returns whether the given error is from a row breaking the unique index with the given name, such
+/// as when two requests at once both try to take the same name.
+pub fn violates_unique_index(error: &sqlx::Error, index: &str) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(|error| error.is_unique_violation() && error.constraint() == Some(index))
+}
+
+/// Applies any database migrations which haven't yet been applied.
+pub async fn migrate(pool: &Pool<Postgres>) -> Result<(), MigrateError> {
+    sqlx::migrate!().run(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    /// Methods which read quotes or comments without filtering them by what the viewer can see.
+    const UNFILTERED: &[&str] = &["fetch_one_for_admin", "fetch_pending_for_quotee"];
+
+    /// Returns the name of each method in the given source which reads from the database without
+    /// filtering by the viewer's visible contexts.
+    fn unfiltered_queries(source: &str) -> Vec<String> {
+        let item =
+            Regex::new(r#"pub async fn (\w+)|sqlx::query\w*(?:::<[^>]*>)?\(\s*"([^"]*)""#).unwrap();
+        let mut method = "";
+        let mut unfiltered = Vec::new();
+        for captures in item.captures_iter(source) {
+            if let Some(name) = captures.get(1) {
+                method = name.as_str();
+            } else if captures[2].trim_start().starts_with("SELECT")
+                && !captures[2].contains("visible_context_ids(")
+                && !UNFILTERED.contains(&method)
+            {
+                unfiltered.push(method.to_owned());
+            }
+        }
+        unfiltered
+    }
+
+    #[test]
+    fn quote_queries_filter_by_viewer() {
+        assert_eq!(
+            unfiltered_queries(include_str!("quote.rs")),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn comment_queries_filter_by_viewer() {
+        assert_eq!(
+            unfiltered_queries(include_str!("comment.rs")),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn unfiltered_queries_found() {
+        let source = r#"
+            pub async fn fetch_good(pool: &Pool<Postgres>) {
+                sqlx::query_as::<_, Self>("SELECT * FROM quotes
+                    WHERE quotes.context_id IN (SELECT * FROM visible_context_ids($1))")
+            }
+            pub async fn fetch_bad(pool: &Pool<Postgres>) {
+                sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM quotes")
+            }
+            pub async fn delete(pool: &Pool<Postgres>) {
+                sqlx::query("DELETE FROM quotes WHERE id = $1")
+            }
+        "#;
+        assert_eq!(unfiltered_queries(source), vec!["fetch_bad"]);
+    }
+}
@@ -0,0 +1,78 @@
+use sqlx::{
+    types::chrono::{DateTime, Utc},
+    FromRow, Pool, Postgres,
+};
+
+/// The most notifications to show a user at once.
+const MAX_NOTIFICATIONS: i64 = 50;
+
+/// A message to a user about something that someone else has done to one of their quotes.
+#[derive(Clone, Debug, FromRow)]
+pub struct Notification {
+    pub message: String,
+    /// The path of a page to go to about the notification, if any.
+    pub link: Option<String>,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    /// Fetches the most recent notifications for the given user, starting with the newest.
+    pub async fn fetch_recent_for_user(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT message, link, read, created_at AT TIME ZONE 'UTC' AS created_at
+             FROM notifications
+             WHERE user_id = $1
+             ORDER BY created_at DESC, id DESC
+             LIMIT $2",
+        )
+        .bind(user_id)
+        .bind(MAX_NOTIFICATIONS)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Counts the things waiting for the given user's attention: unread notifications, and quotes
+    /// about them waiting for their approval.
+    pub async fn count_outstanding(pool: &Pool<Postgres>, user_id: i32) -> sqlx::Result<i64> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT
+               (SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND NOT read)
+               + (SELECT COUNT(*) FROM quotes WHERE quotee_id = $1 AND pending)",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Adds a new notification for the given user.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        message: &str,
+        link: Option<&str>,
+    ) -> sqlx::Result<()> {
+        sqlx::query(
+            "INSERT INTO notifications (user_id, message, link, created_at)
+             VALUES ($1, $2, $3, NOW() AT TIME ZONE 'UTC')",
+        )
+        .bind(user_id)
+        .bind(message)
+        .bind(link)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Marks all of the given user's notifications as read.
+    pub async fn mark_all_read(pool: &Pool<Postgres>, user_id: i32) -> sqlx::Result<()> {
+        sqlx::query("UPDATE notifications SET read = TRUE WHERE user_id = $1 AND NOT read")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
 use super::context::Context;
 use super::user::User;
-use crate::errors::InternalError;
+use crate::{
+    errors::InternalError,
+    pagination::{Cursor, HasCursor},
+};
 use paginate::{Page, Pages};
 use sqlx::{
     postgres::PgRow,
-    types::chrono::{DateTime, Utc},
+    types::chrono::{DateTime, NaiveDate, Utc},
     FromRow, Pool, Postgres, Row,
 };
 
@@ -18,11 +21,63 @@ pub struct Quote {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub hidden: bool,
+    /// Whether the quote is waiting for its quotee to approve it. Pending quotes are also hidden.
+    pub pending: bool,
+    /// Whether the quote was hidden or rejected by its quotee, in which case only they may show it
+    /// again.
+    pub hidden_by_quotee: bool,
 }
 
 impl Quote {
-    /// Fetches the quote with the given ID, if it exists.
-    pub async fn fetch_one(pool: &Pool<Postgres>, quote_id: i32) -> Result<Self, InternalError> {
+    /// Fetches the quote with the given ID, if it exists and the given viewer may see it.
+    pub async fn fetch_one(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        viewer_id: Option<i32>,
+    ) -> Result<Self, InternalError> {
+        sqlx::query_as::<_, Self>(
+            "SELECT quotes.*,
+               quotes.created_at AT TIME ZONE 'UTC' AS created_at,
+               quotes.updated_at AT TIME ZONE 'UTC' AS updated_at
+             FROM quotes
+             WHERE id = $1 AND NOT hidden
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))",
+        )
+        .bind(quote_id)
+        .bind(viewer_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(InternalError::NotFound)
+    }
+
+    /// Fetches the quote with the given ID even if it is hidden, so long as the given viewer may
+    /// see its context. This is only for moderating the quote.
+    pub async fn fetch_one_including_hidden(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        viewer_id: Option<i32>,
+    ) -> Result<Self, InternalError> {
+        sqlx::query_as::<_, Self>(
+            "SELECT quotes.*,
+               quotes.created_at AT TIME ZONE 'UTC' AS created_at,
+               quotes.updated_at AT TIME ZONE 'UTC' AS updated_at
+             FROM quotes
+             WHERE id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))",
+        )
+        .bind(quote_id)
+        .bind(viewer_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(InternalError::NotFound)
+    }
+
+    /// Fetches the quote with the given ID whether or not it is hidden or private. This is only for
+    /// maintenance commands run by the site's administrator.
+    pub async fn fetch_one_for_admin(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+    ) -> Result<Self, InternalError> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
                quotes.created_at AT TIME ZONE 'UTC' AS created_at,
@@ -35,6 +90,154 @@ impl Quote {
         .await?
         .ok_or(InternalError::NotFound)
     }
+
+    /// Adds a new quote, returning its ID. If `pending` is true then the quote is hidden until its
+    /// quotee approves it.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        quote_text: &str,
+        context_id: i32,
+        quoter_id: i32,
+        quotee_id: i32,
+        pending: bool,
+    ) -> sqlx::Result<i32> {
+        sqlx::query_scalar::<_, i32>(
+            "INSERT INTO quotes
+               (quote_text, context_id, quoter_id, quotee_id, created_at, updated_at, hidden,
+                pending)
+             VALUES
+               ($1, $2, $3, $4, NOW() AT TIME ZONE 'UTC', NOW() AT TIME ZONE 'UTC', $5, $5)
+             RETURNING id",
+        )
+        .bind(quote_text)
+        .bind(context_id)
+        .bind(quoter_id)
+        .bind(quotee_id)
+        .bind(pending)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Updates the given quote, marking it as updated now.
+    pub async fn update(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        quote_text: &str,
+        context_id: i32,
+        quotee_id: i32,
+    ) -> sqlx::Result<()> {
+        sqlx::query(
+            "UPDATE quotes
+             SET quote_text = $2,
+               context_id = $3,
+               quotee_id = $4,
+               updated_at = NOW() AT TIME ZONE 'UTC'
+             WHERE id = $1",
+        )
+        .bind(quote_id)
+        .bind(quote_text)
+        .bind(context_id)
+        .bind(quotee_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Hides or unhides the given quote, returning whether it exists. `by_quotee` is whether the
+    /// quote is being hidden by its quotee. Hiding it again doesn't forget that its quotee hid it,
+    /// but unhiding it does.
+    pub async fn set_hidden(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        hidden: bool,
+        by_quotee: bool,
+    ) -> sqlx::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE quotes SET hidden = $2, hidden_by_quotee = $2 AND ($3 OR hidden_by_quotee)
+             WHERE id = $1",
+        )
+        .bind(quote_id)
+        .bind(hidden)
+        .bind(by_quotee)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Hides the given quote until its quotee approves it.
+    pub async fn hold_for_approval(pool: &Pool<Postgres>, quote_id: i32) -> sqlx::Result<()> {
+        sqlx::query("UPDATE quotes SET hidden = TRUE, pending = TRUE WHERE id = $1")
+            .bind(quote_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Shows the given quote if it is waiting for approval, as its quotee no longer needs to
+    /// approve it. It stays hidden if its quotee hid it.
+    pub async fn clear_pending(pool: &Pool<Postgres>, quote_id: i32) -> sqlx::Result<()> {
+        sqlx::query(
+            "UPDATE quotes SET pending = FALSE, hidden = hidden_by_quotee WHERE id = $1 AND pending",
+        )
+        .bind(quote_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Approves or rejects the given pending quote about the given quotee. An approved quote is
+    /// shown, while a rejected one stays hidden until the quotee unhides it.
+    ///
+    /// Returns the quote, or `None` if there is no such quote waiting for approval.
+    pub async fn set_approved(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        quotee_id: i32,
+        approved: bool,
+    ) -> sqlx::Result<Option<Self>> {
+        sqlx::query_as::<_, Self>(
+            "UPDATE quotes SET pending = FALSE, hidden = NOT $3, hidden_by_quotee = NOT $3
+             WHERE id = $1 AND quotee_id = $2 AND pending
+             RETURNING *,
+               created_at AT TIME ZONE 'UTC' AS created_at,
+               updated_at AT TIME ZONE 'UTC' AS updated_at",
+        )
+        .bind(quote_id)
+        .bind(quotee_id)
+        .bind(approved)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Deletes the given quote, along with all comments on it.
+    pub async fn delete(pool: &Pool<Postgres>, quote_id: i32) -> sqlx::Result<()> {
+        let mut transaction = pool.begin().await?;
+        sqlx::query("DELETE FROM comments WHERE quote_id = $1")
+            .bind(quote_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("DELETE FROM quotes WHERE id = $1")
+            .bind(quote_id)
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await
+    }
+}
+
+/// What to search quotes for.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QuoteSearch {
+    /// Words to look for in the text of quotes or their comments, in the syntax of Postgres's
+    /// `websearch_to_tsquery`.
+    pub text: String,
+    /// Only include quotes in this context.
+    pub context_id: Option<i32>,
+    /// Only include quotes of this quotee.
+    pub quotee_id: Option<i32>,
+    /// Only include quotes added on or after this date.
+    pub from: Option<NaiveDate>,
+    /// Only include quotes added on or before this date.
+    pub to: Option<NaiveDate>,
 }
 
 #[derive(Clone, Debug)]
@@ -47,8 +250,12 @@ pub struct QuoteWithUsers {
 }
 
 impl QuoteWithUsers {
-    /// Fetches the quote with the given ID, if it exists.
-    pub async fn fetch_one(pool: &Pool<Postgres>, quote_id: i32) -> Result<Self, InternalError> {
+    /// Fetches the quote with the given ID, if it exists and the given viewer may see it.
+    pub async fn fetch_one(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        viewer_id: Option<i32>,
+    ) -> Result<Self, InternalError> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
                quotes.created_at AT TIME ZONE 'UTC' AS created_at,
@@ -63,21 +270,29 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
-             WHERE quotes.id = $1",
+             WHERE quotes.id = $1 AND NOT hidden
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))",
         )
         .bind(quote_id)
+        .bind(viewer_id)
         .fetch_optional(pool)
         .await?
         .ok_or(InternalError::NotFound)
     }
 
-    /// Fetches all quotes.
-    pub async fn fetch_all(pool: &Pool<Postgres>) -> sqlx::Result<Vec<Self>> {
+    /// Fetches the quote with the given ID, if it was added by the given quoter and they may still
+    /// see it. This includes quotes waiting for approval, so that the quoter can fix them.
+    pub async fn fetch_one_for_quoter(
+        pool: &Pool<Postgres>,
+        quote_id: i32,
+        quoter_id: i32,
+    ) -> Result<Self, InternalError> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
                quotes.created_at AT TIME ZONE 'UTC' AS created_at,
@@ -92,25 +307,34 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
-             WHERE NOT hidden
-             ORDER BY contexts.created_at DESC",
+             WHERE quotes.id = $1 AND quotes.quoter_id = $2
+               AND (NOT hidden OR (pending AND NOT hidden_by_quotee))
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))",
         )
-        .fetch_all(pool)
-        .await
+        .bind(quote_id)
+        .bind(quoter_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(InternalError::NotFound)
     }
 
     /// Returns the number of non-hidden quotes.
-    pub async fn count(pool: &Pool<Postgres>) -> sqlx::Result<usize> {
-        Ok(
-            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM quotes WHERE NOT hidden")
-                .fetch_one(pool)
-                .await? as usize,
+    pub async fn count(pool: &Pool<Postgres>, viewer_id: Option<i32>) -> sqlx::Result<usize> {
+        Ok(sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*)
+             FROM quotes
+             WHERE NOT hidden
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($1))",
         )
+        .bind(viewer_id)
+        .fetch_one(pool)
+        .await? as usize)
     }
 
     /// Fetches all non-hidden quotes within the given page.
@@ -118,6 +342,7 @@ impl QuoteWithUsers {
         pool: &Pool<Postgres>,
         pages: &Pages,
         page: &Page,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -133,25 +358,31 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
              WHERE NOT hidden
-             ORDER BY quotes.created_at DESC
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($3))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
              LIMIT $1 OFFSET $2",
         )
         .bind(pages.limit() as i64)
         .bind(page.start as i64)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
 
-    /// Fetches all non-hidden quotes of the given quotee.
-    pub async fn fetch_all_for_quotee(
+    /// Fetches up to `limit` non-hidden quotes, starting with the most recent, after the given cursor
+    /// if any.
+    pub async fn fetch_after(
         pool: &Pool<Postgres>,
-        quotee_id: i32,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -167,27 +398,40 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
-             WHERE NOT hidden AND quotes.quotee_id = $1
-             ORDER BY quotes.created_at DESC",
-            )
-            .bind(quotee_id)
-            .fetch_all(pool)
-            .await
+             WHERE NOT hidden
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             AND ($1::TIMESTAMP IS NULL OR (quotes.created_at, quotes.id) < ($1, $2))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
+             LIMIT $3",
+        )
+        .bind(after.map(Cursor::created_at_utc))
+        .bind(after.map(|after| after.id))
+        .bind(limit as i64)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
     }
 
     /// Returns the number of non-hidden quotes of the given quotee.
-    pub async fn count_for_quotee(pool: &Pool<Postgres>, quotee_id: i32) -> sqlx::Result<usize> {
+    pub async fn count_for_quotee(
+        pool: &Pool<Postgres>,
+        quotee_id: i32,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<usize> {
         Ok(sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*)
              FROM quotes
-             WHERE NOT hidden AND quotes.quotee_id = $1",
+             WHERE NOT hidden AND quotes.quotee_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))",
         )
         .bind(quotee_id)
+        .bind(viewer_id)
         .fetch_one(pool)
         .await? as usize)
     }
@@ -198,6 +442,7 @@ impl QuoteWithUsers {
         quotee_id: i32,
         pages: &Pages,
         page: &Page,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -213,26 +458,32 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
              WHERE NOT hidden AND quotes.quotee_id = $1
-             ORDER BY quotes.created_at DESC
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
              LIMIT $2 OFFSET $3",
             )
             .bind(quotee_id)
             .bind(pages.limit() as i64)
             .bind(page.start as i64)
+            .bind(viewer_id)
             .fetch_all(pool)
             .await
     }
 
-    /// Fetches all non-hidden quotes in the given context.
-    pub async fn fetch_all_for_context(
+    /// Fetches up to `limit` non-hidden quotes of the given quotee, after the given cursor if any.
+    pub async fn fetch_after_for_quotee(
         pool: &Pool<Postgres>,
-        context_id: i32,
+        quotee_id: i32,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -248,15 +499,23 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
-             WHERE NOT hidden AND quotes.context_id = $1
-             ORDER BY quotes.created_at DESC",
+             WHERE NOT hidden AND quotes.quotee_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($5))
+             AND ($2::TIMESTAMP IS NULL OR (quotes.created_at, quotes.id) < ($2, $3))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
+             LIMIT $4",
             )
-            .bind(context_id)
+            .bind(quotee_id)
+            .bind(after.map(Cursor::created_at_utc))
+            .bind(after.map(|after| after.id))
+            .bind(limit as i64)
+            .bind(viewer_id)
             .fetch_all(pool)
             .await
     }
@@ -265,6 +524,7 @@ impl QuoteWithUsers {
     pub async fn fetch_latest_for_context(
         pool: &Pool<Postgres>,
         context_id: i32,
+        viewer_id: Option<i32>,
     ) -> Result<Self, InternalError> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -280,29 +540,38 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
              WHERE NOT hidden AND quotes.context_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))
              ORDER BY quotes.created_at DESC
              LIMIT 1",
             )
             .bind(context_id)
+            .bind(viewer_id)
             .fetch_optional(pool)
             .await?
             .ok_or(InternalError::NotFound)
     }
 
     /// Returns the number of non-hidden quotes in the given context.
-    pub async fn count_for_context(pool: &Pool<Postgres>, context_id: i32) -> sqlx::Result<usize> {
+    pub async fn count_for_context(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<usize> {
         Ok(sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*)
              FROM quotes
-             WHERE NOT hidden AND quotes.context_id = $1",
+             WHERE NOT hidden AND quotes.context_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))",
         )
         .bind(context_id)
+        .bind(viewer_id)
         .fetch_one(pool)
         .await? as usize)
     }
@@ -313,6 +582,7 @@ impl QuoteWithUsers {
         context_id: i32,
         pages: &Pages,
         page: &Page,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -328,26 +598,32 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
              WHERE NOT hidden AND quotes.context_id = $1
-             ORDER BY quotes.created_at DESC
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
              LIMIT $2 OFFSET $3",
             )
             .bind(context_id)
             .bind(pages.limit() as i64)
             .bind(page.start as i64)
+            .bind(viewer_id)
             .fetch_all(pool)
             .await
     }
 
-    /// Fetches all non-hidden quotes in contexts of which the given user is a member.
-    pub async fn fetch_all_for_user_contexts(
+    /// Fetches up to `limit` non-hidden quotes in the given context, after the given cursor if any.
+    pub async fn fetch_after_for_context(
         pool: &Pool<Postgres>,
-        user_id: i32,
+        context_id: i32,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -363,16 +639,99 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
-               INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
-             WHERE NOT hidden AND contexts_users.user_id = $1
-             ORDER BY quotes.created_at DESC",
+             WHERE NOT hidden AND quotes.context_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($5))
+             AND ($2::TIMESTAMP IS NULL OR (quotes.created_at, quotes.id) < ($2, $3))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
+             LIMIT $4",
+            )
+            .bind(context_id)
+            .bind(after.map(Cursor::created_at_utc))
+            .bind(after.map(|after| after.id))
+            .bind(limit as i64)
+            .bind(viewer_id)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Fetches all hidden quotes in the given context, or only those of the given quotee if one is
+    /// given. Quotes waiting for approval aren't included, as only their quotee may decide on them.
+    pub async fn fetch_hidden_for_context(
+        pool: &Pool<Postgres>,
+        context_id: i32,
+        quotee_id: Option<i32>,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT quotes.*,
+               quotes.created_at AT TIME ZONE 'UTC' AS created_at,
+               quotes.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               (SELECT COUNT(*) FROM comments WHERE comments.quote_id = quotes.id) AS comments_count,
+               quoter.username AS quoter_username,
+               quoter.fullname AS quoter_fullname,
+               quoter.email_address AS quoter_email_address,
+               quoter.openid AS quoter_openid,
+               quotee.username AS quotee_username,
+               quotee.fullname AS quotee_fullname,
+               quotee.email_address AS quotee_email_address,
+               quotee.openid AS quotee_openid,
+               contexts.name AS context_name,
+               contexts.description AS context_description,
+               contexts.private AS context_private
+             FROM quotes
+               INNER JOIN users AS quoter ON quoter.id = quoter_id
+               INNER JOIN users AS quotee ON quotee.id = quotee_id
+               INNER JOIN contexts ON contexts.id = context_id
+             WHERE hidden AND NOT pending AND quotes.context_id = $1
+             AND ($2::INTEGER IS NULL OR quotes.quotee_id = $2)
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($3))
+             ORDER BY quotes.updated_at DESC",
         )
-        .bind(user_id)
+        .bind(context_id)
+        .bind(quotee_id)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Fetches all quotes waiting for the given quotee to approve them, starting with the oldest.
+    ///
+    /// This doesn't take a viewer, as the quotee must be able to see quotes about them to decide
+    /// on them even if they aren't a member of the context.
+    pub async fn fetch_pending_for_quotee(
+        pool: &Pool<Postgres>,
+        quotee_id: i32,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT quotes.*,
+               quotes.created_at AT TIME ZONE 'UTC' AS created_at,
+               quotes.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               (SELECT COUNT(*) FROM comments WHERE comments.quote_id = quotes.id) AS comments_count,
+               quoter.username AS quoter_username,
+               quoter.fullname AS quoter_fullname,
+               quoter.email_address AS quoter_email_address,
+               quoter.openid AS quoter_openid,
+               quotee.username AS quotee_username,
+               quotee.fullname AS quotee_fullname,
+               quotee.email_address AS quotee_email_address,
+               quotee.openid AS quotee_openid,
+               contexts.name AS context_name,
+               contexts.description AS context_description,
+               contexts.private AS context_private
+             FROM quotes
+               INNER JOIN users AS quoter ON quoter.id = quoter_id
+               INNER JOIN users AS quotee ON quotee.id = quotee_id
+               INNER JOIN contexts ON contexts.id = context_id
+             WHERE pending AND quotes.quotee_id = $1
+             ORDER BY quotes.created_at ASC",
+        )
+        .bind(quotee_id)
         .fetch_all(pool)
         .await
     }
@@ -381,14 +740,17 @@ impl QuoteWithUsers {
     pub async fn count_for_user_contexts(
         pool: &Pool<Postgres>,
         user_id: i32,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<usize> {
         Ok(sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*)
              FROM quotes
                INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
-             WHERE NOT hidden AND contexts_users.user_id = $1",
+             WHERE NOT hidden AND contexts_users.user_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($2))",
         )
         .bind(user_id)
+        .bind(viewer_id)
         .fetch_one(pool)
         .await? as usize)
     }
@@ -399,6 +761,7 @@ impl QuoteWithUsers {
         user_id: i32,
         pages: &Pages,
         page: &Page,
+        viewer_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT quotes.*,
@@ -414,19 +777,169 @@ impl QuoteWithUsers {
                quotee.email_address AS quotee_email_address,
                quotee.openid AS quotee_openid,
                contexts.name AS context_name,
-               contexts.description AS context_description
+               contexts.description AS context_description,
+               contexts.private AS context_private
              FROM quotes
                INNER JOIN users AS quoter ON quoter.id = quoter_id
                INNER JOIN users AS quotee ON quotee.id = quotee_id
                INNER JOIN contexts ON contexts.id = context_id
                INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
              WHERE NOT hidden AND contexts_users.user_id = $1
-             ORDER BY quotes.created_at DESC
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($4))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
              LIMIT $2 OFFSET $3",
         )
         .bind(user_id)
         .bind(pages.limit() as i64)
         .bind(page.start as i64)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Fetches up to `limit` non-hidden quotes in contexts of which the given user is a member,
+    /// after the given cursor if any.
+    pub async fn fetch_after_for_user_contexts(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        after: Option<&Cursor>,
+        limit: usize,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT quotes.*,
+               quotes.created_at AT TIME ZONE 'UTC' AS created_at,
+               quotes.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               (SELECT COUNT(*) FROM comments WHERE comments.quote_id = quotes.id) AS comments_count,
+               quoter.username AS quoter_username,
+               quoter.fullname AS quoter_fullname,
+               quoter.email_address AS quoter_email_address,
+               quoter.openid AS quoter_openid,
+               quotee.username AS quotee_username,
+               quotee.fullname AS quotee_fullname,
+               quotee.email_address AS quotee_email_address,
+               quotee.openid AS quotee_openid,
+               contexts.name AS context_name,
+               contexts.description AS context_description,
+               contexts.private AS context_private
+             FROM quotes
+               INNER JOIN users AS quoter ON quoter.id = quoter_id
+               INNER JOIN users AS quotee ON quotee.id = quotee_id
+               INNER JOIN contexts ON contexts.id = context_id
+               INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
+             WHERE NOT hidden AND contexts_users.user_id = $1
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($5))
+             AND ($2::TIMESTAMP IS NULL OR (quotes.created_at, quotes.id) < ($2, $3))
+             ORDER BY quotes.created_at DESC, quotes.id DESC
+             LIMIT $4",
+        )
+        .bind(user_id)
+        .bind(after.map(Cursor::created_at_utc))
+        .bind(after.map(|after| after.id))
+        .bind(limit as i64)
+        .bind(viewer_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns the number of non-hidden quotes matching the given search.
+    pub async fn count_matching(
+        pool: &Pool<Postgres>,
+        search: &QuoteSearch,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<usize> {
+        Ok(sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*)
+             FROM quotes
+             WHERE NOT hidden
+             AND (to_tsvector('english', quotes.quote_text) @@ websearch_to_tsquery('english', $1)
+               OR EXISTS (
+                 SELECT 1 FROM comments
+                 WHERE comments.quote_id = quotes.id
+                   AND to_tsvector('english', comments.body) @@ websearch_to_tsquery('english', $1)
+               ))
+             AND ($2::INTEGER IS NULL OR quotes.context_id = $2)
+             AND ($3::INTEGER IS NULL OR quotes.quotee_id = $3)
+             AND ($4::DATE IS NULL OR quotes.created_at >= $4)
+             AND ($5::DATE IS NULL OR quotes.created_at < $5 + 1)
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($6))",
+        )
+        .bind(&search.text)
+        .bind(search.context_id)
+        .bind(search.quotee_id)
+        .bind(search.from)
+        .bind(search.to)
+        .bind(viewer_id)
+        .fetch_one(pool)
+        .await? as usize)
+    }
+
+    /// Fetches the non-hidden quotes matching the given search within the given page, starting
+    /// with those whose text or best matching comment matches best.
+    pub async fn fetch_page_matching(
+        pool: &Pool<Postgres>,
+        search: &QuoteSearch,
+        pages: &Pages,
+        page: &Page,
+        viewer_id: Option<i32>,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT quotes.*,
+               quotes.created_at AT TIME ZONE 'UTC' AS created_at,
+               quotes.updated_at AT TIME ZONE 'UTC' AS updated_at,
+               (SELECT COUNT(*) FROM comments WHERE comments.quote_id = quotes.id) AS comments_count,
+               quoter.username AS quoter_username,
+               quoter.fullname AS quoter_fullname,
+               quoter.email_address AS quoter_email_address,
+               quoter.openid AS quoter_openid,
+               quotee.username AS quotee_username,
+               quotee.fullname AS quotee_fullname,
+               quotee.email_address AS quotee_email_address,
+               quotee.openid AS quotee_openid,
+               contexts.name AS context_name,
+               contexts.description AS context_description,
+               contexts.private AS context_private
+             FROM quotes
+               INNER JOIN users AS quoter ON quoter.id = quoter_id
+               INNER JOIN users AS quotee ON quotee.id = quotee_id
+               INNER JOIN contexts ON contexts.id = context_id
+             WHERE NOT hidden
+             AND (to_tsvector('english', quotes.quote_text) @@ websearch_to_tsquery('english', $1)
+               OR EXISTS (
+                 SELECT 1 FROM comments
+                 WHERE comments.quote_id = quotes.id
+                   AND to_tsvector('english', comments.body) @@ websearch_to_tsquery('english', $1)
+               ))
+             AND ($2::INTEGER IS NULL OR quotes.context_id = $2)
+             AND ($3::INTEGER IS NULL OR quotes.quotee_id = $3)
+             AND ($4::DATE IS NULL OR quotes.created_at >= $4)
+             AND ($5::DATE IS NULL OR quotes.created_at < $5 + 1)
+             AND quotes.context_id IN (SELECT * FROM visible_context_ids($8))
+             ORDER BY
+               GREATEST(
+                 ts_rank(
+                   to_tsvector('english', quotes.quote_text),
+                   websearch_to_tsquery('english', $1)
+                 ),
+                 (SELECT MAX(ts_rank(
+                     to_tsvector('english', comments.body),
+                     websearch_to_tsquery('english', $1)
+                   ))
+                   FROM comments
+                   WHERE comments.quote_id = quotes.id)
+               ) DESC,
+               quotes.created_at DESC,
+               quotes.id DESC
+             LIMIT $6 OFFSET $7",
+        )
+        .bind(&search.text)
+        .bind(search.context_id)
+        .bind(search.quotee_id)
+        .bind(search.from)
+        .bind(search.to)
+        .bind(pages.limit() as i64)
+        .bind(page.start as i64)
+        .bind(viewer_id)
         .fetch_all(pool)
         .await
     }
@@ -454,9 +967,19 @@ impl<'r> FromRow<'r, PgRow> for QuoteWithUsers {
                 id: row.try_get("context_id")?,
                 name: row.try_get("context_name")?,
                 description: row.try_get("context_description")?,
+                private: row.try_get("context_private")?,
                 quotes_count: 0,
             },
             comments_count: row.try_get("comments_count")?,
         })
     }
 }
+
+impl HasCursor for QuoteWithUsers {
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            created_at: self.quote.created_at,
+            id: self.quote.id,
+        }
+    }
+}
@@ -1,5 +1,17 @@
+use super::member::ContextRole;
 use crate::errors::InternalError;
+use regex::Regex;
 use sqlx::{FromRow, Pool, Postgres};
+use std::sync::LazyLock;
+
+/// Usernames may only contain letters, numbers and a few punctuation characters, must start with a
+/// letter or number, and must be between 3 and 40 characters long.
+static USERNAME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9][A-Za-z0-9_.\-]{2,39}$").unwrap());
+
+/// A very loose check that something looks like an email address.
+static EMAIL_ADDRESS_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap());
 
 #[derive(Clone, Debug, Eq, FromRow, PartialEq)]
 pub struct User {
@@ -11,10 +23,31 @@ pub struct User {
 }
 
 impl User {
+    /// The unique index on usernames, ignoring case.
+    pub const USERNAME_INDEX: &str = "index_users_on_lower_username";
+    /// The unique index on email addresses, ignoring case.
+    pub const EMAIL_ADDRESS_INDEX: &str = "index_users_on_lower_email_address";
+
     pub fn username_or_fullname(&self) -> &str {
         self.username.as_deref().unwrap_or(&self.fullname)
     }
 
+    /// Returns whether this is a placeholder for someone who has been quoted but hasn't yet
+    /// logged in, and so can be claimed by them.
+    pub fn is_placeholder(&self) -> bool {
+        self.email_address.is_none()
+    }
+
+    /// Returns whether the given string is acceptable as a username.
+    pub fn is_valid_username(username: &str) -> bool {
+        USERNAME_REGEX.is_match(username)
+    }
+
+    /// Returns whether the given string looks like an email address.
+    pub fn is_valid_email_address(email_address: &str) -> bool {
+        EMAIL_ADDRESS_REGEX.is_match(email_address)
+    }
+
     /// Fetches the user with the given ID, if they exist.
     pub async fn fetch_one(pool: &Pool<Postgres>, user_id: i32) -> Result<Self, InternalError> {
         sqlx::query_as::<_, Self>("SELECT * FROM users WHERE id = $1")
@@ -24,56 +57,350 @@ impl User {
             .ok_or(InternalError::NotFound)
     }
 
-    /// Fetches the user with the given email address, or `None`.
+    /// Fetches the user with the given email address, or `None`. The comparison is case-insensitive.
     pub async fn fetch_by_email(
         pool: &Pool<Postgres>,
         email_address: &str,
     ) -> Result<Option<Self>, InternalError> {
         Ok(
-            sqlx::query_as::<_, Self>("SELECT * FROM users WHERE email_address = $1")
+            sqlx::query_as::<_, Self>("SELECT * FROM users WHERE LOWER(email_address) = LOWER($1)")
                 .bind(email_address)
                 .fetch_optional(pool)
                 .await?,
         )
     }
 
-    /// Fetches all users.
-    pub async fn fetch_all(pool: &Pool<Postgres>) -> sqlx::Result<Vec<Self>> {
-        sqlx::query_as::<_, Self>("SELECT * FROM users ORDER BY created_at DESC")
-            .fetch_all(pool)
-            .await
+    /// Fetches the user with the given username, or `None`. The comparison is case-insensitive.
+    pub async fn fetch_by_username(
+        pool: &Pool<Postgres>,
+        username: &str,
+    ) -> Result<Option<Self>, InternalError> {
+        Ok(
+            sqlx::query_as::<_, Self>("SELECT * FROM users WHERE LOWER(username) = LOWER($1)")
+                .bind(username)
+                .fetch_optional(pool)
+                .await?,
+        )
     }
 
-    /// Fetches all users who are a member of the given context.
-    pub async fn fetch_all_for_context(
+    /// Fetches the user with the given username or full name, or `None`.
+    ///
+    /// The comparison is case-insensitive, and an exact username match is preferred over a full
+    /// name match.
+    pub async fn fetch_by_name(
         pool: &Pool<Postgres>,
-        context_id: i32,
+        name: &str,
+    ) -> Result<Option<Self>, InternalError> {
+        Ok(sqlx::query_as::<_, Self>(
+            "SELECT * FROM users
+             WHERE LOWER(username) = LOWER($1) OR LOWER(fullname) = LOWER($1)
+             ORDER BY LOWER(username) = LOWER($1) DESC, created_at ASC
+             LIMIT 1",
+        )
+        .bind(name)
+        .fetch_optional(pool)
+        .await?)
+    }
+
+    /// Fetches up to 10 users whose username or full name partly matches any word of the given
+    /// name, for suggesting to the user when no exact match is found.
+    ///
+    /// If a context is given then members of that context are listed first.
+    pub async fn fetch_possible_matches(
+        pool: &Pool<Postgres>,
+        name: &str,
+        context_id: Option<i32>,
     ) -> sqlx::Result<Vec<Self>> {
         sqlx::query_as::<_, Self>(
             "SELECT users.*
              FROM users
-               INNER JOIN contexts_users ON user_id = users.id
-               WHERE context_id = $1
-               ORDER BY users.created_at DESC",
+             WHERE fullname ILIKE ANY($1) OR username ILIKE ANY($1)
+             ORDER BY
+               EXISTS (
+                 SELECT 1 FROM contexts_users
+                 WHERE contexts_users.user_id = users.id AND contexts_users.context_id = $2
+               ) DESC,
+               fullname ASC
+             LIMIT 10",
         )
+        .bind(like_patterns(name))
         .bind(context_id)
         .fetch_all(pool)
         .await
     }
 
-    /// Adds the given user to the given context, if they are not already a member.
+    /// Fetches up to 10 placeholder users whose full name partly matches any word of the given
+    /// name, so that someone signing up can claim one of them.
+    pub async fn fetch_placeholder_matches(
+        pool: &Pool<Postgres>,
+        name: &str,
+    ) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>(
+            "SELECT users.*
+             FROM users
+             WHERE email_address IS NULL AND fullname ILIKE ANY($1)
+             ORDER BY fullname ASC
+             LIMIT 10",
+        )
+        .bind(like_patterns(name))
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Returns whether the given voucher is a member of some context in which the given user has
+    /// been quoted.
+    pub async fn can_vouch_for(
+        pool: &Pool<Postgres>,
+        voucher_id: i32,
+        user_id: i32,
+    ) -> sqlx::Result<bool> {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (
+               SELECT 1 FROM quotes
+                 INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
+               WHERE quotes.quotee_id = $2 AND contexts_users.user_id = $1
+             )",
+        )
+        .bind(voucher_id)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Fetches the IDs of everyone who can vouch for the given user, because they are a member of
+    /// some context in which the user has been quoted.
+    pub async fn fetch_voucher_ids(pool: &Pool<Postgres>, user_id: i32) -> sqlx::Result<Vec<i32>> {
+        sqlx::query_scalar::<_, i32>(
+            "SELECT DISTINCT contexts_users.user_id
+             FROM quotes
+               INNER JOIN contexts_users ON contexts_users.context_id = quotes.context_id
+             WHERE quotes.quotee_id = $1",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Fetches all users.
+    pub async fn fetch_all(pool: &Pool<Postgres>) -> sqlx::Result<Vec<Self>> {
+        sqlx::query_as::<_, Self>("SELECT * FROM users ORDER BY created_at DESC")
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Adds a new user who can log in with the given email address. Fails with a violation of
+    /// [`Self::USERNAME_INDEX`] or [`Self::EMAIL_ADDRESS_INDEX`] if another user already has the
+    /// username or email address.
+    pub async fn create(
+        pool: &Pool<Postgres>,
+        fullname: &str,
+        username: Option<&str>,
+        email_address: &str,
+    ) -> sqlx::Result<Self> {
+        sqlx::query_as::<_, Self>(
+            "INSERT INTO users (fullname, username, email_address, created_at, updated_at)
+             VALUES ($1, $2, $3, NOW() AT TIME ZONE 'UTC', NOW() AT TIME ZONE 'UTC')
+             RETURNING *",
+        )
+        .bind(fullname)
+        .bind(username)
+        .bind(email_address)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Adds a new user with only a full name, for someone who has been quoted but hasn't yet
+    /// logged in.
+    pub async fn create_placeholder(pool: &Pool<Postgres>, fullname: &str) -> sqlx::Result<Self> {
+        sqlx::query_as::<_, Self>(
+            "INSERT INTO users (fullname, created_at, updated_at)
+             VALUES ($1, NOW() AT TIME ZONE 'UTC', NOW() AT TIME ZONE 'UTC')
+             RETURNING *",
+        )
+        .bind(fullname)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Returns whether the given user wants to approve quotes about them before they are shown.
+    pub async fn quotes_need_approval(pool: &Pool<Postgres>, user_id: i32) -> sqlx::Result<bool> {
+        sqlx::query_scalar::<_, bool>("SELECT quotes_need_approval FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Updates the profile details of the given user.
+    ///
+    /// The email address isn't included, as it is how the user logs in, so may only come from
+    /// Google through [`Self::set_email_address`]. Fails with a violation of
+    /// [`Self::USERNAME_INDEX`] if another user already has the username.
+    pub async fn update(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        fullname: &str,
+        username: Option<&str>,
+        quotes_need_approval: bool,
+    ) -> sqlx::Result<()> {
+        sqlx::query(
+            "UPDATE users
+             SET fullname = $2,
+               username = $3,
+               quotes_need_approval = $4,
+               updated_at = NOW() AT TIME ZONE 'UTC'
+             WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(fullname)
+        .bind(username)
+        .bind(quotes_need_approval)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Sets the email address of the given user, which they log in with. It must have been
+    /// verified as theirs. Fails with a violation of [`Self::EMAIL_ADDRESS_INDEX`] if another user
+    /// already has it.
+    pub async fn set_email_address(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        email_address: &str,
+    ) -> sqlx::Result<()> {
+        sqlx::query(
+            "UPDATE users
+             SET email_address = $2, updated_at = NOW() AT TIME ZONE 'UTC'
+             WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(email_address)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Sets the email address of the given placeholder user, so that whoever logs in with that
+    /// email address will become that user. This must only be done on behalf of someone who can
+    /// vouch for the user.
+    ///
+    /// Returns false if the user doesn't exist or isn't a placeholder. Fails with a violation of
+    /// [`Self::EMAIL_ADDRESS_INDEX`] if another user already has the email address.
+    pub async fn claim(
+        pool: &Pool<Postgres>,
+        user_id: i32,
+        email_address: &str,
+    ) -> sqlx::Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users
+             SET email_address = $2, updated_at = NOW() AT TIME ZONE 'UTC'
+             WHERE id = $1 AND email_address IS NULL",
+        )
+        .bind(user_id)
+        .bind(email_address)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Moves all quotes, comments, context memberships, invitations, notifications and API tokens of
+    /// one user to another, then deletes the first user.
+    ///
+    /// Any email address, username or OpenID of the deleted user is kept if the remaining user
+    /// doesn't already have one. If both users are members of the same context, the more privileged
+    /// of their roles is kept.
+    pub async fn merge(
+        pool: &Pool<Postgres>,
+        from_user_id: i32,
+        into_user_id: i32,
+    ) -> sqlx::Result<()> {
+        let mut transaction = pool.begin().await?;
+        sqlx::query("UPDATE quotes SET quoter_id = $2 WHERE quoter_id = $1")
+            .bind(from_user_id)
+            .bind(into_user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE quotes SET quotee_id = $2 WHERE quotee_id = $1")
+            .bind(from_user_id)
+            .bind(into_user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE comments SET user_id = $2 WHERE user_id = $1")
+            .bind(from_user_id)
+            .bind(into_user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE invitations SET created_by = $2 WHERE created_by = $1")
+            .bind(from_user_id)
+            .bind(into_user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE notifications SET user_id = $2 WHERE user_id = $1")
+            .bind(from_user_id)
+            .bind(into_user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE api_tokens SET user_id = $2 WHERE user_id = $1")
+            .bind(from_user_id)
+            .bind(into_user_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query(
+            "INSERT INTO contexts_users (user_id, context_id, role)
+             SELECT $2, context_id, role FROM contexts_users
+             WHERE user_id = $1
+             ON CONFLICT (context_id, user_id) DO UPDATE
+             SET role = CASE
+               WHEN 'owner' IN (contexts_users.role, EXCLUDED.role) THEN 'owner'
+               WHEN 'admin' IN (contexts_users.role, EXCLUDED.role) THEN 'admin'
+               ELSE 'member'
+             END",
+        )
+        .bind(from_user_id)
+        .bind(into_user_id)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query("DELETE FROM contexts_users WHERE user_id = $1")
+            .bind(from_user_id)
+            .execute(&mut *transaction)
+            .await?;
+        let from_user = sqlx::query_as::<_, Self>("DELETE FROM users WHERE id = $1 RETURNING *")
+            .bind(from_user_id)
+            .fetch_one(&mut *transaction)
+            .await?;
+        sqlx::query(
+            "UPDATE users
+             SET email_address = COALESCE(email_address, $2),
+               username = COALESCE(username, $3),
+               openid = COALESCE(openid, $4),
+               updated_at = NOW() AT TIME ZONE 'UTC'
+             WHERE id = $1
+             RETURNING id",
+        )
+        .bind(into_user_id)
+        .bind(from_user.email_address)
+        .bind(from_user.username)
+        .bind(from_user.openid)
+        .fetch_one(&mut *transaction)
+        .await?;
+        transaction.commit().await
+    }
+
+    /// Adds the given user to the given context with the given role, if they are not already a
+    /// member.
     pub async fn join_context(
         pool: &Pool<Postgres>,
         user_id: i32,
         context_id: i32,
+        role: ContextRole,
     ) -> sqlx::Result<()> {
         sqlx::query(
-            "INSERT INTO contexts_users (user_id, context_id)
-             VALUES ($1, $2)
+            "INSERT INTO contexts_users (user_id, context_id, role)
+             VALUES ($1, $2, $3)
              ON CONFLICT DO NOTHING",
         )
         .bind(user_id)
         .bind(context_id)
+        .bind(role)
         .execute(pool)
         .await?;
         Ok(())
@@ -96,3 +423,52 @@ impl User {
         Ok(())
     }
 }
+
+/// Returns an `ILIKE` pattern for each word of the given name, matching any string containing that
+/// word.
+fn like_patterns(name: &str) -> Vec<String> {
+    name.split_whitespace()
+        .map(|word| {
+            let escaped = word
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_usernames() {
+        assert!(User::is_valid_username("dave"));
+        assert!(User::is_valid_username("Dave_Smith-2.0"));
+        assert!(!User::is_valid_username(""));
+        assert!(!User::is_valid_username("ab"));
+        assert!(!User::is_valid_username("_dave"));
+        assert!(!User::is_valid_username("dave smith"));
+        assert!(!User::is_valid_username(&"a".repeat(41)));
+    }
+
+    #[test]
+    fn valid_email_addresses() {
+        assert!(User::is_valid_email_address("dave@example.com"));
+        assert!(!User::is_valid_email_address("dave"));
+        assert!(!User::is_valid_email_address("dave@example"));
+        assert!(!User::is_valid_email_address("dave smith@example.com"));
+    }
+
+    #[test]
+    fn like_patterns_words() {
+        assert_eq!(like_patterns("  Dave  Smith "), vec!["%Dave%", "%Smith%"]);
+        assert_eq!(like_patterns(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn like_patterns_escaped() {
+        assert_eq!(like_patterns("50%_a\\b"), vec!["%50\\%\\_a\\\\b%"]);
+    }
+}
@@ -1,12 +1,54 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use paginate::{Page, Pages};
-use serde::Deserialize;
-use std::cmp::min;
+use serde::{de, Deserialize, Deserializer};
+use sqlx::types::chrono::{DateTime, NaiveDateTime, Utc};
+use std::{
+    cmp::min,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use utoipa::IntoParams;
+
+/// The number of quotes or comments in each page of the listings of all of them.
+pub const LIST_PAGE_SIZE: usize = 20;
+/// The number of pages to link to either side of the current page.
+const PAGINATION_WINDOW: usize = 2;
 
 /// Query parameter for pagination.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, IntoParams, PartialEq)]
+#[into_params(parameter_in = Query)]
 pub struct QueryPage {
+    /// The page to show, numbered from 0.
+    #[serde(default)]
+    pub page: usize,
+}
+
+/// Query parameters for pagination of a listing, which may be by page number or by cursor.
+#[derive(Clone, Debug, Default, Deserialize, Eq, IntoParams, PartialEq)]
+#[into_params(parameter_in = Query)]
+pub struct QueryListPage {
+    /// The page to show, numbered from 0. This is ignored if `after` is given.
     #[serde(default)]
     pub page: usize,
+    /// Show the page after this cursor, as given for an earlier page, rather than a numbered page.
+    /// This stays fast however deep into the listing the page is.
+    #[serde(default)]
+    #[param(value_type = Option<String>)]
+    pub after: Option<Cursor>,
+}
+
+/// Query parameters for a page of a feed, which may be a page of the paged view by cursor or an
+/// archive document.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct QueryFeedPage {
+    /// Show the page of the paged view after this cursor, rather than the first page.
+    #[serde(default)]
+    pub after: Option<Cursor>,
+    /// Show the archive document with this number, counting from 0 for the oldest. This takes
+    /// precedence over `after`.
+    #[serde(default)]
+    pub archive: Option<usize>,
 }
 
 pub struct PaginationState {
@@ -14,9 +56,25 @@ pub struct PaginationState {
     pub current_page: Page,
     /// The number of pages to include either side of the current page.
     pub window_size: usize,
+    /// Any other query parameters of the current page, already URL-encoded, to keep in links to
+    /// other pages.
+    pub other_params: String,
 }
 
 impl PaginationState {
+    /// Splits a list of `length` items into pages of `per_page` items, with the page at the given
+    /// offset as the current page.
+    pub fn new(length: usize, per_page: usize, page: usize) -> Self {
+        let pages = Pages::new(length, per_page);
+        let current_page = pages.with_offset(page);
+        Self {
+            pages,
+            current_page,
+            window_size: PAGINATION_WINDOW,
+            other_params: String::new(),
+        }
+    }
+
     pub fn page_links(&self) -> Vec<PageOrGap> {
         let mut links = vec![];
 
@@ -71,34 +129,169 @@ pub enum PageOrGap {
     Gap,
 }
 
+/// The position of a quote or comment in a listing ordered by when they were created, from which
+/// to continue onto the next page.
+///
+/// This is given to users as an opaque string, so that its format can change.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: i32,
+}
+
+impl Cursor {
+    /// Returns the creation time in the form stored in the database.
+    pub fn created_at_utc(&self) -> NaiveDateTime {
+        self.created_at.naive_utc()
+    }
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let plain = format!("{}:{}", self.created_at.timestamp_micros(), self.id);
+        f.write_str(&URL_SAFE_NO_PAD.encode(plain))
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = InvalidCursor;
+
+    fn from_str(s: &str) -> Result<Self, InvalidCursor> {
+        let plain = String::from_utf8(URL_SAFE_NO_PAD.decode(s).map_err(|_| InvalidCursor)?)
+            .map_err(|_| InvalidCursor)?;
+        let (micros, id) = plain.split_once(':').ok_or(InvalidCursor)?;
+        Ok(Self {
+            created_at: DateTime::from_timestamp_micros(micros.parse().map_err(|_| InvalidCursor)?)
+                .ok_or(InvalidCursor)?,
+            id: id.parse().map_err(|_| InvalidCursor)?,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidCursor;
+
+impl Display for InvalidCursor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("Invalid cursor")
+    }
+}
+
+impl Error for InvalidCursor {}
+
+/// Something in a listing which may be paginated by cursor.
+pub trait HasCursor {
+    /// Returns the cursor from which to continue after this item.
+    fn cursor(&self) -> Cursor;
+}
+
+/// The pagination of a listing which may be paginated by page number or by cursor.
+pub struct ListPagination {
+    /// The numbered pages, or `None` if the page was found from a cursor so the listing wasn't
+    /// counted.
+    pub numbered: Option<PaginationState>,
+    /// The cursor for the next page, if there is one.
+    pub next: Option<Cursor>,
+}
+
+impl ListPagination {
+    /// Returns the items of a numbered page, along with their pagination.
+    pub fn numbered<T: HasCursor>(items: Vec<T>, numbered: PaginationState) -> (Vec<T>, Self) {
+        let next = if numbered.current_page.offset + 1 < numbered.pages.page_count() {
+            items.last().map(HasCursor::cursor)
+        } else {
+            None
+        };
+        (
+            items,
+            Self {
+                numbered: Some(numbered),
+                next,
+            },
+        )
+    }
+
+    /// Returns the items of a page found from a cursor, along with their pagination.
+    ///
+    /// The items should have been fetched with one more than `per_page`, to tell whether there is a
+    /// next page; this is removed.
+    pub fn after_cursor<T: HasCursor>(mut items: Vec<T>, per_page: usize) -> (Vec<T>, Self) {
+        let next = if items.len() > per_page {
+            items.truncate(per_page);
+            items.last().map(HasCursor::cursor)
+        } else {
+            None
+        };
+        (
+            items,
+            Self {
+                numbered: None,
+                next,
+            },
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    impl HasCursor for i32 {
+        fn cursor(&self) -> Cursor {
+            Cursor {
+                created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+                id: *self,
+            }
+        }
+    }
+
     #[test]
-    fn single_page() {
-        let pages = Pages::new(7, 10);
-        let current_page = pages.with_offset(0);
-        let state = PaginationState {
-            pages,
-            current_page: current_page.clone(),
-            window_size: 2,
+    fn cursor_round_trip() {
+        let cursor = Cursor {
+            created_at: DateTime::from_timestamp_micros(1_760_745_600_123_456).unwrap(),
+            id: 42,
         };
+        assert_eq!(cursor.to_string().parse(), Ok(cursor));
+        assert_eq!("not a cursor".parse::<Cursor>(), Err(InvalidCursor));
+    }
+
+    #[test]
+    fn next_cursor() {
+        let (items, pagination) = ListPagination::after_cursor(vec![5, 4, 3], 2);
+        assert_eq!(items, vec![5, 4]);
+        assert_eq!(pagination.next, Some(4.cursor()));
+
+        let (_, pagination) = ListPagination::after_cursor(vec![2, 1], 2);
+        assert_eq!(pagination.next, None);
+
+        let (_, pagination) = ListPagination::numbered(vec![9, 8], PaginationState::new(5, 2, 0));
+        assert_eq!(pagination.next, Some(8.cursor()));
+
+        let (_, pagination) = ListPagination::numbered(vec![1], PaginationState::new(5, 2, 2));
+        assert_eq!(pagination.next, None);
+    }
+
+    #[test]
+    fn single_page() {
+        let state = PaginationState::new(7, 10, 0);
         assert_eq!(
             state.page_links(),
-            vec![PageOrGap::CurrentPage(current_page)]
+            vec![PageOrGap::CurrentPage(state.current_page.clone())]
         );
     }
 
     #[test]
     fn no_gaps() {
-        let pages = Pages::new(50, 10);
-        let current_page = pages.with_offset(2);
-        let state = PaginationState {
-            pages: pages.clone(),
-            current_page: current_page.clone(),
-            window_size: 2,
-        };
+        let state = PaginationState::new(50, 10, 2);
+        let pages = state.pages.clone();
         assert_eq!(
             state.page_links(),
             vec![
@@ -113,13 +306,8 @@ mod tests {
 
     #[test]
     fn gaps() {
-        let pages = Pages::new(100, 10);
-        let current_page = pages.with_offset(5);
-        let state = PaginationState {
-            pages: pages.clone(),
-            current_page: current_page.clone(),
-            window_size: 2,
-        };
+        let state = PaginationState::new(100, 10, 5);
+        let pages = state.pages.clone();
         assert_eq!(
             state.page_links(),
             vec![
@@ -138,13 +326,8 @@ mod tests {
 
     #[test]
     fn almost_gaps() {
-        let pages = Pages::new(70, 10);
-        let current_page = pages.with_offset(3);
-        let state = PaginationState {
-            pages: pages.clone(),
-            current_page: current_page.clone(),
-            window_size: 2,
-        };
+        let state = PaginationState::new(70, 10, 3);
+        let pages = state.pages.clone();
         assert_eq!(
             state.page_links(),
             vec![
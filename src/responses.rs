@@ -1,20 +1,248 @@
-use atom_syndication::Feed;
+use crate::{
+    errors::InternalError,
+    feed::{json::JsonFeedDocument, FeedFormat},
+};
 use axum::{
     body::Body,
-    http::{header, HeaderValue},
-    response::{IntoResponse, Response},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
 };
+use sha2::{Digest, Sha256};
+use std::fmt::Debug;
 
+/// An Atom feed.
 #[derive(Clone, Debug)]
-pub struct Atom(pub Feed);
+pub struct Atom(pub atom_syndication::Feed);
 
 impl IntoResponse for Atom {
     fn into_response(self) -> Response {
-        let mut res = Response::new(Body::from(self.0.to_string()));
-        res.headers_mut().insert(
-            header::CONTENT_TYPE,
-            HeaderValue::from_static("application/atom+xml"),
-        );
+        feed_response(self.0.to_string(), Self::MIME_TYPE)
+    }
+}
+
+/// An RSS 2.0 feed.
+#[derive(Clone, Debug)]
+pub struct Rss(pub rss::Channel);
+
+impl IntoResponse for Rss {
+    fn into_response(self) -> Response {
+        feed_response(self.0.to_string(), Self::MIME_TYPE)
+    }
+}
+
+/// A JSON Feed.
+#[derive(Clone, Debug)]
+pub struct JsonFeed(pub JsonFeedDocument);
+
+impl IntoResponse for JsonFeed {
+    fn into_response(self) -> Response {
+        match serde_json::to_string(&self.0) {
+            Ok(body) => feed_response(body, Self::MIME_TYPE),
+            Err(e) => InternalError::from(e).into_response(),
+        }
+    }
+}
+
+/// Returns a response with the given feed.
+fn feed_response(body: String, content_type: &'static str) -> Response {
+    let mut res = Response::new(Body::from(body));
+    res.headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    res
+}
+
+/// A response which is sent with an `ETag` computed from the data it is rendered from, rather than
+/// from its body, so that it needn't be rendered at all if the client's copy is still current.
+#[derive(Clone, Debug)]
+pub struct Validated<T> {
+    etag: String,
+    /// The rendered response, or `None` to reply `304 Not Modified`.
+    body: Option<T>,
+}
+
+impl<T> Validated<T> {
+    /// Returns a response with an `ETag` computed from the given data, which must include
+    /// everything the response depends on. It is only rendered from the data if the conditional
+    /// headers of the request don't show that the client already has it.
+    pub fn new<D: Debug, E>(
+        data: D,
+        request_headers: &HeaderMap,
+        render: impl FnOnce(D) -> Result<T, E>,
+    ) -> Result<Self, E> {
+        let etag = etag(&format!("{:?}", data));
+        let body = match HeaderValue::from_str(&etag) {
+            Ok(value) if !is_modified(request_headers, Some(&value)) => None,
+            _ => Some(render(data)?),
+        };
+        Ok(Self { etag, body })
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for Validated<T> {
+    fn into_response(self) -> Response {
+        let mut res = match self.body {
+            Some(body) => body.into_response(),
+            None => StatusCode::NOT_MODIFIED.into_response(),
+        };
+        insert_cache_headers(res.headers_mut(), &self.etag);
         res
     }
 }
+
+/// An HTML page which is sent with an `ETag`, so that browsers can make conditional requests for
+/// it.
+#[derive(Clone, Debug)]
+pub struct CacheableHtml(pub String);
+
+impl IntoResponse for CacheableHtml {
+    fn into_response(self) -> Response {
+        let etag = etag(&self.0);
+        let mut res = Html(self.0).into_response();
+        insert_cache_headers(res.headers_mut(), &etag);
+        res
+    }
+}
+
+/// Returns an entity tag for a response with the given body, from its hash.
+fn etag(body: &str) -> String {
+    format!("\"{:x}\"", Sha256::digest(body.as_bytes()))
+}
+
+/// Adds the given `ETag` to a response.
+///
+/// The response may be different for each user, so it mustn't be stored by shared caches, and
+/// browsers must check that it is still current before using a copy they have stored.
+fn insert_cache_headers(headers: &mut HeaderMap, etag: &str) {
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, no-cache"),
+    );
+    if let Ok(etag) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, etag);
+    }
+}
+
+/// Middleware to reply `304 Not Modified` without a body to a conditional `GET` or `HEAD` request
+/// if the response has an `ETag` which shows that the client's copy is still current.
+///
+/// The page is still fetched and rendered as usual, but isn't sent again. Responses which are
+/// expensive to render should use [`Validated`] to avoid that.
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    let conditional = matches!(*request.method(), Method::GET | Method::HEAD);
+    let request_headers = request.headers().clone();
+    let response = next.run(request).await;
+
+    if conditional
+        && response.status() == StatusCode::OK
+        && !is_modified(&request_headers, response.headers().get(header::ETAG))
+    {
+        let mut not_modified = Response::new(Body::empty());
+        *not_modified.status_mut() = StatusCode::NOT_MODIFIED;
+        for name in [header::CACHE_CONTROL, header::ETAG, header::SET_COOKIE] {
+            for value in response.headers().get_all(&name) {
+                not_modified.headers_mut().append(&name, value.clone());
+            }
+        }
+        not_modified
+    } else {
+        response
+    }
+}
+
+/// Returns whether a response with the given `ETag` is different to the client's copy, according
+/// to the `If-None-Match` header of the request as described in RFC 9110 section 13.
+///
+/// Responses aren't sent with a `Last-Modified` time, as hiding or deleting an entry of a listing
+/// changes it without anything having a later modification time, so `If-Modified-Since` is ignored.
+fn is_modified(request_headers: &HeaderMap, etag: Option<&HeaderValue>) -> bool {
+    let Some(if_none_match) = request_headers.get(header::IF_NONE_MATCH) else {
+        return true;
+    };
+    let Some(etag) = etag else {
+        return true;
+    };
+    let etag = weak_etag(etag.as_bytes());
+    !if_none_match
+        .as_bytes()
+        .split(|&byte| byte == b',')
+        .any(|tag| {
+            let tag = tag.trim_ascii();
+            tag == b"*" || weak_etag(tag) == etag
+        })
+}
+
+/// Returns the opaque part of the given entity tag, without any `W/` prefix, for the weak
+/// comparison which is used for `If-None-Match`.
+fn weak_etag(tag: &[u8]) -> &[u8] {
+    tag.strip_prefix(b"W/").unwrap_or(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(entries: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+        entries
+            .iter()
+            .map(|(name, value)| (name.clone(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn matching_etag_is_not_modified() {
+        let etag = HeaderValue::from_static("\"abc\"");
+        assert!(!is_modified(
+            &headers(&[(header::IF_NONE_MATCH, "\"xyz\", W/\"abc\"")]),
+            Some(&etag)
+        ));
+        assert!(!is_modified(
+            &headers(&[(header::IF_NONE_MATCH, "*")]),
+            Some(&etag)
+        ));
+        assert!(is_modified(
+            &headers(&[(header::IF_NONE_MATCH, "\"xyz\"")]),
+            Some(&etag)
+        ));
+        assert!(is_modified(&HeaderMap::new(), Some(&etag)));
+        assert!(is_modified(
+            &headers(&[(header::IF_NONE_MATCH, "\"abc\"")]),
+            None
+        ));
+    }
+
+    #[test]
+    fn modified_since_is_ignored() {
+        assert!(is_modified(
+            &headers(&[(header::IF_MODIFIED_SINCE, "Sun, 18 Oct 2026 10:00:00 GMT")]),
+            Some(&HeaderValue::from_static("\"abc\""))
+        ));
+    }
+
+    #[test]
+    fn validated_only_renders_when_modified() {
+        let data = ("Quotes", vec![1, 2, 3]);
+        let first = Validated::new(&data, &HeaderMap::new(), |_| Ok::<_, ()>("feed")).unwrap();
+        assert_eq!(first.body, Some("feed"));
+
+        let if_none_match = HeaderMap::from_iter([(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&first.etag).unwrap(),
+        )]);
+        let again = Validated::new(&data, &if_none_match, |_| -> Result<&str, ()> {
+            panic!("rendered again")
+        })
+        .unwrap();
+        assert_eq!(again.body, None);
+        let response = again.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], first.etag);
+
+        let changed = Validated::new(&("Quotes", vec![1, 2]), &if_none_match, |_| {
+            Ok::<_, ()>("feed")
+        })
+        .unwrap();
+        assert_eq!(changed.body, Some("feed"));
+    }
+}
@@ -1,7 +1,11 @@
-use crate::{config::Config, errors::InternalError, model::User};
+use crate::{
+    config::Config,
+    errors::InternalError,
+    model::{ApiToken, Notification, User},
+};
 use axum::{
     extract::{Extension, FromRequestParts, OriginalUri},
-    http::request::Parts,
+    http::{header, request::Parts},
 };
 use eyre::eyre;
 use jsonwebtoken::{decode, DecodingKey, Validation};
@@ -13,10 +17,20 @@ use std::{
 };
 use tower_cookies::{Cookie, Cookies};
 
+/// The prefix of the paths of the API, which is the only part of the site that API tokens can be
+/// used for.
+const API_PATH: &str = "/api/";
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct Session {
     pub flash: Flash,
     pub current_user: Option<User>,
+    /// Whether the current user is an admin.
+    pub admin: bool,
+    /// The number of unread notifications and quotes waiting for approval which the current user
+    /// has. Only HTML pages show this, so it is only counted for requests which accept HTML, and is
+    /// 0 for others such as API calls and feed polls.
+    pub notification_count: i64,
     // The path of the current page.
     pub path: String,
 }
@@ -26,6 +40,12 @@ impl Session {
         self.current_user.is_some()
     }
 
+    /// Returns the ID of the current user, if any. This is who database queries should filter
+    /// private contexts for.
+    pub fn current_user_id(&self) -> Option<i32> {
+        self.current_user.as_ref().map(|user| user.id)
+    }
+
     pub fn is_current_user(&self, &user_id: &i32) -> bool {
         if let Some(current_user) = &self.current_user {
             current_user.id == user_id
@@ -45,10 +65,35 @@ impl<S: Send + Sync> FromRequestParts<S> for Session {
         let Extension(config) = Extension::<Arc<Config>>::from_request_parts(parts, state).await?;
         let Extension(pool) = Extension::<Pool<Postgres>>::from_request_parts(parts, state).await?;
         let OriginalUri(uri) = OriginalUri::from_request_parts(parts, state).await?;
-        let current_user = user_from_cookies(&config, &pool, cookies).await;
+        // Scripts using an API token don't have a session cookie. An invalid token is an error
+        // rather than being treated as being logged out, so that the script can tell. Tokens are
+        // only for the API, so that a leaked token can't be used to manage the account, including
+        // making more tokens.
+        let current_user = match bearer_token(parts) {
+            Some(_) if !uri.path().starts_with(API_PATH) => {
+                return Err(InternalError::Unauthorised)
+            }
+            Some(token) => Some(
+                user_from_api_token(&pool, token)
+                    .await?
+                    .ok_or(InternalError::Unauthorised)?,
+            ),
+            None => user_from_cookies(&config, &pool, cookies).await,
+        };
+        let admin = current_user
+            .as_ref()
+            .is_some_and(|user| config.is_admin(user.id));
+        let notification_count = match &current_user {
+            Some(user) if accepts_html(parts) => {
+                Notification::count_outstanding(&pool, user.id).await?
+            }
+            _ => 0,
+        };
         Ok(Session {
             flash: Flash::from_request_parts(parts, state).await?,
             current_user,
+            admin,
+            notification_count,
             path: uri
                 .path_and_query()
                 .ok_or_else(|| InternalError::Internal(eyre!("Request URI missing path")))?
@@ -97,6 +142,38 @@ async fn user_from_cookies(
     User::fetch_one(pool, data.claims.sub).await.ok()
 }
 
+/// Returns whether the request's `Accept` header allows an HTML response, as browsers send for
+/// pages and forms.
+fn accepts_html(parts: &Parts) -> bool {
+    parts
+        .headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|accept| accept.to_str().ok())
+        .any(|accept| accept.contains("text/html"))
+}
+
+/// Returns the token from the `Authorization: Bearer` header of the request, if there is one.
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+async fn user_from_api_token(
+    pool: &Pool<Postgres>,
+    token: &str,
+) -> Result<Option<User>, InternalError> {
+    match ApiToken::use_token(pool, token).await? {
+        Some(user_id) => Ok(Some(User::fetch_one(pool, user_id).await?)),
+        None => Ok(None),
+    }
+}
+
 /// Claims for our session token.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SessionClaims {
@@ -119,3 +196,41 @@ impl SessionClaims {
         })
     }
 }
+
+/// Claims for the token identifying someone who has logged in with Google but doesn't yet have an
+/// account, while they fill in the sign-up form.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SignupClaims {
+    iat: u64,
+    exp: u64,
+    /// The verified email address from Google.
+    pub email: String,
+    /// The name from Google.
+    pub name: String,
+}
+
+impl SignupClaims {
+    pub fn new(
+        email: String,
+        name: String,
+        issued: SystemTime,
+        expiry: SystemTime,
+    ) -> Result<Self, SystemTimeError> {
+        Ok(Self {
+            email,
+            name,
+            iat: issued.duration_since(SystemTime::UNIX_EPOCH)?.as_secs(),
+            exp: expiry.duration_since(SystemTime::UNIX_EPOCH)?.as_secs(),
+        })
+    }
+
+    /// Returns the valid signup claims from the `signup` cookie, if there are any.
+    pub fn from_cookies(config: &Config, cookies: &Cookies) -> Option<Self> {
+        let signup_token = cookies.get("signup")?;
+        let key = DecodingKey::from_secret(config.secret.as_bytes());
+        let validation = Validation::default();
+        decode::<SignupClaims>(signup_token.value(), &key, &validation)
+            .ok()
+            .map(|data| data.claims)
+    }
+}